@@ -1,36 +1,33 @@
-use intcomputer;
+use intcomputer::symbolic::{Solver, Target, Unknown};
 use std::env;
-use std::fs;
 
-fn run_program(program: &Vec<i32>, noun: i32, verb: i32) -> i32 {
+// Instructions after which a candidate is assumed to loop forever
+const BUDGET: u64 = 100_000;
+
+fn run_program(program: &Vec<i64>, noun: i64, verb: i64) -> Option<i64> {
     let mut prog = program.clone();
     prog[1] = noun;
     prog[2] = verb;
     let mut comp = intcomputer::IntComputer::new(prog);
-    comp.run();
-    comp.program[0]
+    comp.set_budget(Some(BUDGET));
+    match comp.run() {
+        Ok(intcomputer::ProgramState::Finished) => Some(comp.program[0]),
+        _ => None,
+    }
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let filename = &args[1];
-    let contents = fs::read_to_string(filename).unwrap();
+    let program = intcomputer::load_program(filename).unwrap();
 
-    let program: Vec<i32> = contents
-        .trim()
-        .split(',')
-        .map(|x| x.parse().unwrap())
-        .collect();
+    println!("Solution Part 1: {}", run_program(&program, 12, 2).unwrap());
 
-    println!("Solution Part 1: {}", run_program(&program, 12, 2));
-
-    for noun in 0..=99 {
-        for verb in 0..=100 {
-            let result = run_program(&program, noun, verb);
-            if result == 19690720 {
-                println!("Solution Part 2: {}{}", noun, verb);
-                break;
-            }
-        }
-    }
+    // Noun and verb are solved for symbolically, falling back to trying every pair
+    let mut solver = Solver::new(program);
+    solver.set_budget(BUDGET);
+    solver.add_unknown(Unknown::Cell(1), 0..=99);
+    solver.add_unknown(Unknown::Cell(2), 0..=99);
+    let solution = solver.solve(Target::Cell(0), 19690720).unwrap();
+    println!("Solution Part 2: {}", 100 * solution[0] + solution[1]);
 }
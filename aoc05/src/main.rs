@@ -1,25 +1,18 @@
-use intcomputer::IntComputer;
+use intcomputer::{load_program, IntComputer};
 use std::env;
-use std::fs;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let filename = &args[1];
-    let contents = fs::read_to_string(filename).unwrap();
-
-    let program: Vec<i32> = contents
-        .trim()
-        .split(',')
-        .map(|x| x.parse().unwrap())
-        .collect();
+    let program = load_program(filename).unwrap();
 
     let mut comp = IntComputer::new(program.clone());
     comp.input.push_back(1);
-    comp.run();
+    comp.run().unwrap();
     println!("Solution Part 1: {:?}", comp.output);
 
     let mut comp = IntComputer::new(program);
     comp.input.push_back(5);
-    comp.run();
+    comp.run().unwrap();
     println!("Solution Part 2: {:?}", comp.output);
 }
@@ -1,59 +1,37 @@
-use intcomputer::{IntComputer, ProgramState};
+use intcomputer::{load_program, IntComputer, Network, Pipeline, Ring, Topology};
 use std::env;
-use std::fs;
 
 use itertools::Itertools;
 
-fn run_pipeline(program: &Vec<i32>, sequence: Vec<i32>) -> i32 {
-    let mut io = 0;
-    for i in 0..=4 {
-        let mut comp = IntComputer::new(program.clone());
-        comp.input.push_back(sequence[i]);
-        comp.input.push_back(io);
-        comp.run();
-        io = comp.output[0]
-    }
-    io
-}
-
-fn run_pipeline_feedback(program: &Vec<i32>, sequence: Vec<i32>) -> i32 {
-    let mut computers: Vec<IntComputer> = vec![];
-    for i in 0..=4 {
-        let mut comp = IntComputer::new(program.clone());
-        comp.input.push_back(sequence[i]);
-        computers.push(comp);
-    }
-
-    computers[0].input.push_back(0);
-
-    let mut idx = 0;
-    loop {
-        let c = &mut computers[idx];
-        c.run();
-        let io = c.output.pop_front().unwrap();
+// Run one amplifier per phase setting, feeding 0 into the first one
+fn run_amplifiers<T: Topology>(program: &Vec<i64>, sequence: Vec<i64>, topology: T) -> i64 {
+    let computers = sequence
+        .iter()
+        .map(|&phase| {
+            let mut comp = IntComputer::new(program.clone());
+            comp.input.push_back(phase);
+            comp
+        })
+        .collect();
 
-        if computers.iter().all(|c| c.state == ProgramState::Finished) {
-            return io;
-        }
+    let mut network = Network::new(computers, topology);
+    network.machine_mut(0).input.push_back(0);
+    network.run().unwrap();
+    *network.output.back().unwrap()
+}
 
-        idx += 1;
-        idx %= 5;
+fn run_pipeline(program: &Vec<i64>, sequence: Vec<i64>) -> i64 {
+    run_amplifiers(program, sequence, Pipeline)
+}
 
-        let c = &mut computers[idx];
-        c.input.push_back(io);
-    }
+fn run_pipeline_feedback(program: &Vec<i64>, sequence: Vec<i64>) -> i64 {
+    run_amplifiers(program, sequence, Ring)
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let filename = &args[1];
-    let contents = fs::read_to_string(filename).unwrap();
-
-    let program: Vec<i32> = contents
-        .trim()
-        .split(',')
-        .map(|x| x.parse().unwrap())
-        .collect();
+    let program = load_program(filename).unwrap();
 
     let max_thrust = (0..=4)
         .into_iter()
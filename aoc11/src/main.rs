@@ -1,7 +1,7 @@
+use intcomputer::executor::{self, LocalExecutor};
 use intcomputer::*;
 use std::collections::HashMap;
 use std::env;
-use std::fs;
 
 enum Direction {
     Up,
@@ -25,7 +25,7 @@ struct Position {
 impl Eq for Position {}
 
 struct PaintingRobot {
-    computer: IntComputer,
+    program: Vec<i64>,
     pos: Position,
     dir: Direction,
     painted: HashMap<Position, Color>,
@@ -34,7 +34,7 @@ struct PaintingRobot {
 impl PaintingRobot {
     pub fn new(program: Vec<i64>) -> PaintingRobot {
         PaintingRobot {
-            computer: IntComputer::new(program),
+            program,
             pos: Position { x: 0, y: 0 },
             dir: Direction::Up,
             painted: HashMap::new(),
@@ -42,17 +42,25 @@ impl PaintingRobot {
     }
 
     pub fn run(&mut self) {
-        loop {
-            self.computer.run();
-            match self.computer.state {
-                ProgramState::Finished => break,
-                ProgramState::Running => continue,
-                ProgramState::WaitingForInput => {
-                    self.consume_output();
-                    self.computer.input.push_back(self.read_color());
-                }
+        let mut computer = IntComputer::new(self.program.clone());
+        let (to_computer, mut input) = executor::channel();
+        let (mut output, mut from_computer) = executor::channel();
+
+        let mut executor = LocalExecutor::new();
+        executor.spawn(async move {
+            let state = computer.run_async(&mut input, &mut output).await;
+            assert_eq!(state, Ok(ProgramState::Finished));
+        });
+        executor.spawn(async {
+            to_computer.send(self.read_color());
+            while let Some(color) = from_computer.recv().await {
+                let turn = from_computer.recv().await;
+                self.handle_output(color, turn);
+                to_computer.send(self.read_color());
             }
-        }
+        });
+        let stalled = executor.run();
+        assert_eq!(stalled, 0, "robot and computer are waiting for each other");
     }
 
     pub fn turn_left(&mut self) {
@@ -82,21 +90,19 @@ impl PaintingRobot {
         }
     }
 
-    pub fn consume_output(&mut self) {
-        while self.computer.output.len() > 0 {
-            let col = match self.computer.output.pop_front() {
-                Some(0) => Color::Black,
-                Some(1) => Color::White,
-                _ => panic!("Unexpected Output"),
-            };
-            self.paint(col);
-            match self.computer.output.pop_front() {
-                Some(0) => self.turn_left(),
-                Some(1) => self.turn_right(),
-                _ => panic!("Unexpected Output"),
-            }
-            self.move_forward();
+    pub fn handle_output(&mut self, color: i64, turn: Option<i64>) {
+        let col = match color {
+            0 => Color::Black,
+            1 => Color::White,
+            _ => panic!("Unexpected Output"),
+        };
+        self.paint(col);
+        match turn {
+            Some(0) => self.turn_left(),
+            Some(1) => self.turn_right(),
+            _ => panic!("Unexpected Output"),
         }
+        self.move_forward();
     }
 
     pub fn paint(&mut self, c: Color) {
@@ -114,13 +120,7 @@ impl PaintingRobot {
 fn main() {
     let args: Vec<String> = env::args().collect();
     let filename = &args[1];
-    let contents = fs::read_to_string(filename).unwrap();
-
-    let program: Vec<i64> = contents
-        .trim()
-        .split(',')
-        .map(|x| x.parse().unwrap())
-        .collect();
+    let program = load_program(filename).unwrap();
 
     let mut robot = PaintingRobot::new(program.clone());
     robot.run();
@@ -2,7 +2,6 @@ use intcomputer::*;
 use display::*;
 
 use std::env;
-use std::fs;
 
 use num_enum::TryFromPrimitive;
 use std::convert::TryFrom;
@@ -40,10 +39,12 @@ impl<'a> Arcade<'a> {
 
     pub fn run(&mut self) {
         loop {
-            self.computer.run();
+            self.computer.run().unwrap();
             match self.computer.state {
-                ProgramState::Finished => break,
-                ProgramState::Running => continue,
+                ProgramState::Finished | ProgramState::BudgetExhausted => break,
+                ProgramState::Running
+                | ProgramState::Breakpoint(_)
+                | ProgramState::Watchpoint(_) => continue,
                 ProgramState::WaitingForInput => {
                     self.consume_output();
                     break;
@@ -83,15 +84,10 @@ impl<'a> Arcade<'a> {
 fn main() -> Result<(), String> {
     let args: Vec<String> = env::args().collect();
     let filename = &args[1];
-    let contents = fs::read_to_string(filename).unwrap();
 
     let mut display= Display::new(WIDTH, HEIGHT, SCALE, "Aoc Day 13");
 
-    let mut program: Vec<i64> = contents
-        .trim()
-        .split(',')
-        .map(|x| x.parse().unwrap())
-        .collect();
+    let mut program = load_program(filename).unwrap();
 
         let mut arcade = Arcade::new(program.clone(), &mut display);
         arcade.run();
@@ -1,6 +1,5 @@
-use intcomputer::IntComputer;
+use intcomputer::{load_program, IntComputer};
 use std::env;
-use std::fs;
 
 #[derive(Debug, Eq, PartialEq)]
 enum Direction {
@@ -99,7 +98,7 @@ impl Robot {
             Direction::West => self.computer.input.push_back(3),
             Direction::East => self.computer.input.push_back(4),
         }
-        self.computer.run();
+        self.computer.run().unwrap();
         let ret = match self.computer.output.pop_front().unwrap() {
             0 => Tile::Wall,
             1 => Tile::Empty,
@@ -128,17 +127,11 @@ impl Robot {
 fn main() {
     let args: Vec<String> = env::args().collect();
     let filename = &args[1];
-    let contents = fs::read_to_string(filename).unwrap();
-
-    let program: Vec<i64> = contents
-        .trim()
-        .split(',')
-        .map(|x| x.parse().unwrap())
-        .collect();
+    let program = load_program(filename).unwrap();
 
     let mut comp = IntComputer::new(program.clone());
     comp.input.push_back(1);
-    comp.run();
+    comp.run().unwrap();
     println!("Solution Part 1: {:?}", comp.output);
 
     let mut comp = IntComputer::new(program);
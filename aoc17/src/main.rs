@@ -1,7 +1,6 @@
-use intcomputer::{IntComputer, ProgramState};
+use intcomputer::{load_program, IntComputer, ProgramState};
 use std::collections::HashSet;
 use std::env;
-use std::fs;
 
 #[derive(Debug, PartialEq)]
 enum AbsDirection {
@@ -88,45 +87,31 @@ impl Robot<'_> {
 fn main() {
     let args: Vec<String> = env::args().collect();
     let filename = &args[1];
-    let contents = fs::read_to_string(filename).unwrap();
-
-    let program: Vec<i64> = contents
-        .trim()
-        .split(',')
-        .map(|x| x.parse().unwrap())
-        .collect();
+    let program = load_program(filename).unwrap();
 
     let mut computer = IntComputer::new(program);
     while computer.state == ProgramState::Running {
-        computer.run();
+        computer.run().unwrap();
     }
 
     // Draw to console
-    let output: String = computer
-        .output
-        .clone()
-        .iter()
-        .map(|&x| (x as u8) as char)
-        .collect();
+    let (output, _) = computer.drain_ascii();
     println!("{}", output);
 
     // Store walkable tiles
     let mut scaffolding: HashSet<(usize, usize)> = HashSet::new();
-    let output_slice = computer.output.as_slices().0;
 
     // Split along newlines
-    let rows: Vec<&[i64]> = output_slice.split(|&x| x == 10).collect();
+    let rows: Vec<&str> = output.split('\n').collect();
     let num_rows = rows.len();
     let num_cols = rows[0].len();
 
     let mut starting_position = (0, 0);
     for (y, row) in rows.iter().enumerate() {
-        for (x, &c) in row.iter().enumerate() {
-            // '#' => insert in Hashset
-            if c == 35 {
+        for (x, c) in row.chars().enumerate() {
+            if c == '#' {
                 scaffolding.insert((x, y));
-            // '^' => starting position
-            } else if c == 94 {
+            } else if c == '^' {
                 starting_position = (x, y);
             }
         }
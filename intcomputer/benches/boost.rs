@@ -0,0 +1,42 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use intcomputer::{CompiledEngine, IntComputer, PagedMemory, ProgramState};
+
+// BOOST program from day 9, running its self test in mode 1 and the sensor boost in mode 2
+const BOOST: &str = include_str!("../../aoc09/input");
+
+fn program() -> Vec<i64> {
+    intcomputer::parse_program(BOOST).unwrap()
+}
+
+fn run(mut comp: IntComputer<impl intcomputer::Memory>, mode: i64) -> i64 {
+    comp.input.push_back(mode);
+    assert_eq!(comp.run(), Ok(ProgramState::Finished));
+    comp.output[0]
+}
+
+fn boost(c: &mut Criterion) {
+    let program = program();
+    c.bench_function("boost self test", |b| {
+        b.iter(|| run(IntComputer::new(program.clone()), 1))
+    });
+    c.bench_function("boost sensor", |b| {
+        b.iter(|| run(IntComputer::new(program.clone()), 2))
+    });
+    c.bench_function("boost sensor compiled", |b| {
+        b.iter(|| {
+            let mut comp = IntComputer::new(program.clone());
+            comp.input.push_back(2);
+            assert_eq!(
+                CompiledEngine::new().run(&mut comp),
+                Ok(ProgramState::Finished)
+            );
+            comp.output[0]
+        })
+    });
+    c.bench_function("boost sensor paged", |b| {
+        b.iter(|| run(IntComputer::with_memory(PagedMemory::new(&program)), 2))
+    });
+}
+
+criterion_group!(benches, boost);
+criterion_main!(benches);
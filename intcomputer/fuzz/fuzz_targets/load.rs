@@ -0,0 +1,12 @@
+// Loading arbitrary bytes fails with an error instead of panicking, and binary programs
+// round-trip
+#![no_main]
+
+use intcomputer::{decode_program, encode_binary};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(program) = decode_program(data) {
+        assert_eq!(decode_program(&encode_binary(&program)).unwrap(), program);
+    }
+});
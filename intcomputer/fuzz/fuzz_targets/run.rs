@@ -0,0 +1,41 @@
+// Run arbitrary programs on the interpreter and the compiled engine and compare them with the
+// reference interpreter of the property tests
+#![no_main]
+
+#[path = "../../tests/reference/mod.rs"]
+mod reference;
+
+use intcomputer::{CompiledEngine, IntComputer, ProgramState};
+use libfuzzer_sys::fuzz_target;
+use reference::Reference;
+
+const BUDGET: u64 = 100_000;
+const MEMORY_LIMIT: usize = 1 << 16;
+
+fuzz_target!(|data: (Vec<i64>, Vec<i64>)| {
+    let (program, input) = data;
+
+    let mut comp = IntComputer::new(program.clone());
+    comp.set_budget(Some(BUDGET));
+    comp.set_memory_limit(Some(MEMORY_LIMIT));
+    comp.input.extend(&input);
+    let result = comp.run();
+
+    let mut expected = Reference::new(program.clone());
+    expected.memory_limit = MEMORY_LIMIT;
+    expected.input.extend(&input);
+    assert_eq!(result, expected.run(BUDGET));
+    assert_eq!(comp.pc(), expected.pc);
+    assert_eq!(comp.program, expected.memory);
+    assert_eq!(comp.output, expected.output);
+
+    // The compiled engine has no budget, only run it on programs known to terminate
+    if result != Ok(ProgramState::BudgetExhausted) {
+        let mut compiled = IntComputer::new(program);
+        compiled.set_memory_limit(Some(MEMORY_LIMIT));
+        compiled.input.extend(&input);
+        assert_eq!(CompiledEngine::new().run(&mut compiled), result);
+        assert_eq!(compiled.program, comp.program);
+        assert_eq!(compiled.output, comp.output);
+    }
+});
@@ -0,0 +1,79 @@
+use crate::{IntComputer, Memory};
+
+impl<M: Memory> IntComputer<M> {
+    // Queue a line of text as input, terminated by a newline
+    pub fn push_line(&mut self, line: &str) {
+        self.input.extend(line.bytes().map(i64::from));
+        self.input.push_back(10);
+    }
+
+    // Take the next complete line of ASCII output without the newline
+    // None if the output doesn't start with a complete line of ASCII characters
+    pub fn read_line(&mut self) -> Option<String> {
+        let len = self
+            .output
+            .iter()
+            .take_while(|&&value| is_ascii(value))
+            .position(|&value| value == 10)?;
+        let line = self
+            .output
+            .drain(..len)
+            .map(|value| value as u8 as char)
+            .collect();
+        self.output.pop_front();
+        Some(line)
+    }
+
+    // Take all output, separating ASCII text from other values like the final result of a
+    // program
+    pub fn drain_ascii(&mut self) -> (String, Vec<i64>) {
+        let mut text = String::new();
+        let mut values = vec![];
+        for value in self.output.drain(..) {
+            if is_ascii(value) {
+                text.push(value as u8 as char);
+            } else {
+                values.push(value);
+            }
+        }
+        (text, values)
+    }
+}
+
+fn is_ascii(value: i64) -> bool {
+    (0..128).contains(&value)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::ECHO;
+    use crate::{IntComputer, ProgramState};
+
+    #[test]
+    fn test_lines() {
+        let mut comp = IntComputer::new(ECHO.to_vec());
+        comp.push_line("A,B,C");
+        comp.push_line("");
+        comp.input.push_back(65);
+        assert_eq!(comp.run(), Ok(ProgramState::WaitingForInput));
+
+        assert_eq!(comp.read_line(), Some("A,B,C".to_string()));
+        assert_eq!(comp.read_line(), Some("".to_string()));
+        // The last line is incomplete
+        assert_eq!(comp.read_line(), None);
+        assert_eq!(comp.output, vec![65]);
+    }
+
+    #[test]
+    fn test_drain_ascii() {
+        let mut comp = IntComputer::new(ECHO.to_vec());
+        comp.push_line("ok");
+        comp.input.extend(&[19690720, 0]);
+        assert_eq!(comp.run(), Ok(ProgramState::Finished));
+        assert_eq!(comp.read_line(), Some("ok".to_string()));
+        // Non-ASCII values stop line reading
+        assert_eq!(comp.read_line(), None);
+        assert_eq!(comp.drain_ascii(), ("\0".to_string(), vec![19690720]));
+        assert!(comp.output.is_empty());
+    }
+}
@@ -0,0 +1,287 @@
+use crate::{Instruction, ParameterMode};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+// Error in the assembler source, lines are counted from 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsmError {
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Number(i64),
+    Label(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Operand {
+    mode: ParameterMode,
+    value: Value,
+}
+
+// Parsed source line, addresses are resolved once all labels are known
+enum Item {
+    Instruction(Instruction, Vec<Operand>),
+    Data(Vec<Value>),
+}
+
+// Assemble source text into a program
+//
+// Each line holds an optional label (`loop:`), then an instruction or a `.data` directive.
+// Operands are `#imm`, `[pos]` or `[rb+off]`; labels may be used in place of numbers and
+// bare operands are immediates. The written operand may be separated by `->` instead of a
+// comma and comments start with `;`. Numeric labels like `0012:` assert the current address,
+// which allows feeding disassembler listings back into the assembler.
+pub fn assemble(source: &str) -> Result<Vec<i64>, AsmError> {
+    let mut labels: HashMap<String, i64> = HashMap::new();
+    let mut items: Vec<(usize, Item)> = vec![];
+    let mut addr = 0;
+
+    for (idx, line) in source.lines().enumerate() {
+        let lineno = idx + 1;
+        let err = |message: String| AsmError {
+            line: lineno,
+            message,
+        };
+
+        let mut rest = line.split(';').next().unwrap().trim();
+        while let Some(pos) = rest.find(':') {
+            let label = rest[..pos].trim();
+            if let Ok(expected) = label.parse::<usize>() {
+                if expected != addr {
+                    return Err(err(format!(
+                        "address {} does not match current address {}",
+                        expected, addr
+                    )));
+                }
+            } else if is_identifier(label) {
+                if labels.insert(label.to_string(), addr as i64).is_some() {
+                    return Err(err(format!("duplicate label '{}'", label)));
+                }
+            } else {
+                return Err(err(format!("invalid label '{}'", label)));
+            }
+            rest = rest[pos + 1..].trim();
+        }
+        if rest.is_empty() {
+            continue;
+        }
+
+        let (mnemonic, args) = match rest.find(char::is_whitespace) {
+            Some(pos) => (&rest[..pos], rest[pos..].trim()),
+            None => (rest, ""),
+        };
+        // Instructions without inputs are written as `in -> [addr]`
+        let args: Vec<&str> = match args.trim_start_matches("->").trim() {
+            "" => vec![],
+            args => args
+                .split("->")
+                .flat_map(|part| part.split(','))
+                .map(|arg| arg.trim())
+                .collect(),
+        };
+
+        if mnemonic.eq_ignore_ascii_case(".data") {
+            let values = args
+                .iter()
+                .map(|arg| parse_value(arg).ok_or_else(|| err(format!("invalid value '{}'", arg))))
+                .collect::<Result<Vec<Value>, AsmError>>()?;
+            addr += values.len();
+            items.push((lineno, Item::Data(values)));
+            continue;
+        }
+
+        let instr = Instruction::from_mnemonic(mnemonic)
+            .ok_or_else(|| err(format!("unknown mnemonic '{}'", mnemonic)))?;
+        if args.len() != instr.len() - 1 {
+            return Err(err(format!(
+                "{} takes {} operands, got {}",
+                instr.mnemonic(),
+                instr.len() - 1,
+                args.len()
+            )));
+        }
+        let operands = args
+            .iter()
+            .map(|arg| parse_operand(arg).ok_or_else(|| err(format!("invalid operand '{}'", arg))))
+            .collect::<Result<Vec<Operand>, AsmError>>()?;
+        if instr.writes() && operands.last().unwrap().mode == ParameterMode::Immediate {
+            return Err(err(format!(
+                "{} can't write to an immediate",
+                instr.mnemonic()
+            )));
+        }
+        addr += instr.len();
+        items.push((lineno, Item::Instruction(instr, operands)));
+    }
+
+    let mut program = Vec::with_capacity(addr);
+    for (lineno, item) in items {
+        let resolve = |value: &Value| match value {
+            Value::Number(n) => Ok(*n),
+            Value::Label(label) => labels.get(label).cloned().ok_or_else(|| AsmError {
+                line: lineno,
+                message: format!("undefined label '{}'", label),
+            }),
+        };
+        match item {
+            Item::Instruction(instr, operands) => {
+                let mut opcode = instr.code();
+                for (i, operand) in operands.iter().enumerate() {
+                    let mode = match operand.mode {
+                        ParameterMode::Immediate => 1,
+                        ParameterMode::Relative => 2,
+                        _ => 0,
+                    };
+                    opcode += mode * 10i64.pow(i as u32 + 2);
+                }
+                program.push(opcode);
+                for operand in operands.iter() {
+                    program.push(resolve(&operand.value)?);
+                }
+            }
+            Item::Data(values) => {
+                for value in values.iter() {
+                    program.push(resolve(value)?);
+                }
+            }
+        }
+    }
+    Ok(program)
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_value(s: &str) -> Option<Value> {
+    if let Ok(n) = s.parse() {
+        return Some(Value::Number(n));
+    }
+    if is_identifier(s) {
+        return Some(Value::Label(s.to_string()));
+    }
+    None
+}
+
+fn parse_operand(s: &str) -> Option<Operand> {
+    if let Some(imm) = s.strip_prefix('#') {
+        return Some(Operand {
+            mode: ParameterMode::Immediate,
+            value: parse_value(imm.trim())?,
+        });
+    }
+    if let Some(inner) = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        let inner = inner.trim();
+        let offset = ["rb", "r"].iter().find_map(|reg| {
+            let off = inner.strip_prefix(reg)?.trim();
+            match off.chars().next() {
+                None => Some(Value::Number(0)),
+                Some('+') => parse_value(off[1..].trim()),
+                Some('-') => parse_value(&off.replace(' ', "")),
+                Some(_) => None,
+            }
+        });
+        return Some(match offset {
+            Some(value) => Operand {
+                mode: ParameterMode::Relative,
+                value,
+            },
+            None => Operand {
+                mode: ParameterMode::Position,
+                value: parse_value(inner)?,
+            },
+        });
+    }
+    Some(Operand {
+        mode: ParameterMode::Immediate,
+        value: parse_value(s)?,
+    })
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for AsmError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{disasm, IntComputer};
+
+    #[test]
+    fn test_encoding() {
+        let program = assemble(
+            "
+            add #3, [4], [rb+100]
+            mul [r-2], #-1 -> [7]
+            in [rb]
+            out #42
+            hlt
+            ",
+        )
+        .unwrap();
+        assert_eq!(
+            program,
+            vec![20101, 3, 4, 100, 1202, -2, -1, 7, 203, 0, 104, 42, 99]
+        );
+    }
+
+    #[test]
+    fn test_labels() {
+        // Count down from the input value, printing every step
+        let program = assemble(
+            "
+            in [counter]
+        loop:
+            out [counter]
+            add [counter], #-1, [counter]
+            jt [counter], loop       ; bare labels are immediates
+            hlt
+        counter: .data 0
+            ",
+        )
+        .unwrap();
+
+        let mut comp = IntComputer::new(program);
+        comp.input.push_back(3);
+        comp.run().unwrap();
+        assert_eq!(comp.output, vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = |source: &str| assemble(source).unwrap_err();
+        assert_eq!(err("nop").message, "unknown mnemonic 'nop'");
+        assert_eq!(err("hlt\nadd #1, #2").line, 2);
+        assert_eq!(
+            err("add #1, #2, #3").message,
+            "ADD can't write to an immediate"
+        );
+        assert_eq!(err("jt #1, nowhere").message, "undefined label 'nowhere'");
+        assert_eq!(err("a: hlt\na: hlt").message, "duplicate label 'a'");
+        assert_eq!(err("out [r+]").message, "invalid operand '[r+]'");
+        assert_eq!(
+            err("0001: hlt").message,
+            "address 1 does not match current address 0"
+        );
+    }
+
+    #[test]
+    fn test_disassembly_roundtrip() {
+        let program = vec![1105, 1, 6, 42, 43, 44, 203, -3, 99];
+        let listing = disasm::disassemble(&program).to_string();
+        assert_eq!(assemble(&listing).unwrap(), program);
+    }
+}
@@ -0,0 +1,269 @@
+use crate::exec::{self, Machine, Word};
+use crate::{Dialect, IntcodeError, ProgramState, Stats, DEFAULT_MEMORY_LIMIT};
+use num_bigint::BigInt;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::collections::VecDeque;
+
+// Interpreter computing with arbitrary-precision integers for programs exceeding i64
+// Shares the instruction set with IntComputer, including memory limits, dialects and stats,
+// but observers, breakpoints and the I/O traits work on i64 and are left to IntComputer
+// Opcodes and addresses still have to fit machine integers, otherwise it fails with Overflow
+pub struct BigComputer {
+    pub program: Vec<BigInt>,
+    pub input: VecDeque<BigInt>,
+    pub output: VecDeque<BigInt>,
+    pub state: ProgramState,
+    base: BigInt,
+    pc: usize,
+    memory_limit: Option<usize>,
+    dialect: Dialect,
+    stats: Stats,
+}
+
+impl BigComputer {
+    pub fn new(program: Vec<BigInt>) -> BigComputer {
+        BigComputer {
+            program,
+            input: VecDeque::new(),
+            output: VecDeque::new(),
+            state: ProgramState::Running,
+            base: BigInt::zero(),
+            pc: 0,
+            memory_limit: Some(DEFAULT_MEMORY_LIMIT),
+            dialect: Dialect::default(),
+            stats: Stats::default(),
+        }
+    }
+
+    pub fn from_i64(program: &[i64]) -> BigComputer {
+        BigComputer::new(program.iter().map(|&value| BigInt::from(value)).collect())
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn base(&self) -> &BigInt {
+        &self.base
+    }
+
+    // Fail with MemoryLimitExceeded instead of allocating more than limit cells
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.memory_limit = limit;
+    }
+
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit
+    }
+
+    // Reject opcodes and parameter modes outside of dialect
+    pub fn set_dialect(&mut self, dialect: Dialect) {
+        self.dialect = dialect;
+    }
+
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    // Run program until it halts or waits for input
+    pub fn run(&mut self) -> Result<ProgramState, IntcodeError> {
+        self.step()?;
+        while self.state == ProgramState::Running {
+            self.exec_instr()?;
+        }
+        Ok(self.state)
+    }
+
+    pub fn step(&mut self) -> Result<ProgramState, IntcodeError> {
+        self.state = ProgramState::Running;
+        self.exec_instr()?;
+        Ok(self.state)
+    }
+
+    fn exec_instr(&mut self) -> Result<(), IntcodeError> {
+        let value = exec::fetch(self)?;
+        let opcode = exec::decode(value, self.pc, &self.dialect)?;
+        exec::execute(self, opcode)?;
+        if self.state != ProgramState::WaitingForInput {
+            self.stats.record(opcode.instr);
+        }
+        Ok(())
+    }
+}
+
+impl Word for BigInt {
+    fn from_bool(value: bool) -> BigInt {
+        if value {
+            BigInt::one()
+        } else {
+            BigInt::zero()
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        Zero::is_zero(self)
+    }
+
+    fn is_negative(&self) -> bool {
+        Signed::is_negative(self)
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        ToPrimitive::to_i64(self)
+    }
+
+    fn to_usize(&self) -> Option<usize> {
+        ToPrimitive::to_usize(self)
+    }
+}
+
+impl Machine for BigComputer {
+    type Word = BigInt;
+
+    fn pc(&self) -> usize {
+        self.pc
+    }
+
+    fn set_pc(&mut self, pc: usize) {
+        self.pc = pc;
+    }
+
+    fn base(&self) -> BigInt {
+        self.base.clone()
+    }
+
+    fn set_base(&mut self, base: BigInt) {
+        self.base = base;
+    }
+
+    fn set_state(&mut self, state: ProgramState) {
+        self.state = state;
+    }
+
+    fn word(&self, addr: usize) -> Option<BigInt> {
+        self.program.get(addr).cloned()
+    }
+
+    fn read_cell(&mut self, addr: usize) -> BigInt {
+        self.stats.access(addr);
+        self.program.get(addr).cloned().unwrap_or_default()
+    }
+
+    fn write_cell(&mut self, addr: usize, value: BigInt) -> Result<(), IntcodeError> {
+        if addr >= self.program.len() {
+            match self.memory_limit {
+                Some(limit) if addr + 1 > limit => {
+                    return Err(IntcodeError::MemoryLimitExceeded {
+                        pc: self.pc,
+                        address: addr,
+                        limit,
+                    })
+                }
+                _ => self.program.resize(addr + 1, BigInt::zero()),
+            }
+        }
+        self.stats.access(addr);
+        self.program[addr] = value;
+        Ok(())
+    }
+
+    fn add(&self, a: BigInt, b: BigInt) -> Result<BigInt, IntcodeError> {
+        Ok(a + b)
+    }
+
+    fn mul(&self, a: BigInt, b: BigInt) -> Result<BigInt, IntcodeError> {
+        Ok(a * b)
+    }
+
+    fn peek_input(&mut self) -> Option<BigInt> {
+        self.input.front().cloned()
+    }
+
+    fn consume_input(&mut self) {
+        self.input.pop_front();
+    }
+
+    fn emit_output(&mut self, value: BigInt) {
+        self.output.push_back(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_large_numbers() {
+        // 2^32 * 2^32 overflows i64
+        let mut comp = BigComputer::from_i64(&[1102, 1 << 32, 1 << 32, 7, 4, 7, 99, 0]);
+        assert_eq!(comp.run(), Ok(ProgramState::Finished));
+        assert_eq!(comp.output, vec![BigInt::from(1u128 << 64)]);
+
+        // Square the input twice
+        let mut comp = BigComputer::from_i64(&[3, 13, 2, 13, 13, 13, 2, 13, 13, 13, 4, 13, 99]);
+        comp.input.push_back(BigInt::from(1u64 << 40));
+        comp.run().unwrap();
+        assert_eq!(comp.output, vec![BigInt::from(1u64 << 40).pow(4)]);
+    }
+
+    #[test]
+    fn test_day9_examples() {
+        let quine = [
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ];
+        let mut comp = BigComputer::from_i64(&quine);
+        comp.run().unwrap();
+        assert_eq!(comp.output, BigComputer::from_i64(&quine).program);
+
+        let mut comp = BigComputer::from_i64(&[104, 1125899906842624, 99]);
+        comp.run().unwrap();
+        assert_eq!(comp.output, vec![BigInt::from(1125899906842624i64)]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut comp = BigComputer::from_i64(&[109, -10, 204, 0, 99]);
+        assert_eq!(
+            comp.run(),
+            Err(IntcodeError::NegativeAddress {
+                pc: 2,
+                address: -10
+            })
+        );
+
+        // Jump to 2^64
+        let mut comp = BigComputer::from_i64(&[1105, 1, 0, 99]);
+        comp.program[2] = BigInt::from(1u128 << 64);
+        assert_eq!(comp.run(), Err(IntcodeError::Overflow { pc: 0 }));
+    }
+
+    #[test]
+    fn test_shared_settings() {
+        let mut comp = BigComputer::from_i64(&[1101, 1, 1, 100, 99]);
+        comp.set_memory_limit(Some(100));
+        assert_eq!(
+            comp.run(),
+            Err(IntcodeError::MemoryLimitExceeded {
+                pc: 0,
+                address: 100,
+                limit: 100
+            })
+        );
+
+        let mut comp = BigComputer::from_i64(&[3, 0, 99]);
+        comp.set_dialect(Dialect::Day2);
+        assert_eq!(
+            comp.run(),
+            Err(IntcodeError::UnknownOpcode { pc: 0, value: 3 })
+        );
+
+        let mut comp = BigComputer::from_i64(&[1101, 1, 1, 5, 99, 0]);
+        comp.run().unwrap();
+        assert_eq!(comp.stats().count(crate::Instruction::Add), 1);
+        assert_eq!(comp.stats().count(crate::Instruction::Halt), 1);
+    }
+}
@@ -0,0 +1,254 @@
+use intcomputer::{disasm, load_program, IntComputer, ProgramState};
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::process;
+use std::str::FromStr;
+
+const HELP: &str = "\
+s, step [n]          execute n instructions (default 1)
+bs, back [n]         undo n instructions (default 1)
+rb, rback <addr>     undo instructions until pc reaches addr
+c, continue          run until halt, input request, breakpoint or watchpoint
+b, break <addr>      set breakpoint
+d, delete <addr>     clear breakpoint
+w, watch <addr>      stop when the value at addr changes
+u, unwatch <addr>    remove watchpoint
+info                 list breakpoints and watchpoints
+x <addr> [n]         print n memory cells starting at addr (default 1)
+poke <addr> <value>  write value to memory
+l, list [addr] [n]   disassemble n instructions from addr (default pc, 10)
+r, regs              show pc, relative base and state
+stats                show execution statistics
+pc <addr>            set program counter
+base <value>         set relative base
+i, input <v>...      queue input values
+o, output            print and clear output
+h, help              show this help
+q, quit              exit
+An empty line repeats the previous command.";
+
+// Number of instructions that can be undone
+const HISTORY: usize = 1_000_000;
+
+struct Debugger {
+    computer: IntComputer,
+}
+
+fn arg<T: FromStr>(args: &[&str], idx: usize, name: &str) -> Result<T, String> {
+    let arg = args
+        .get(idx)
+        .ok_or_else(|| format!("missing argument <{}>", name))?;
+    arg.parse()
+        .map_err(|_| format!("invalid {} '{}'", name, arg))
+}
+
+fn opt_arg<T: FromStr>(args: &[&str], idx: usize, name: &str, default: T) -> Result<T, String> {
+    match args.get(idx) {
+        Some(_) => arg(args, idx, name),
+        None => Ok(default),
+    }
+}
+
+impl Debugger {
+    fn new(program: Vec<i64>) -> Self {
+        let mut computer = IntComputer::new(program);
+        computer.set_history_limit(Some(HISTORY));
+        Self { computer }
+    }
+
+    // Execute a command line, returns false once the user wants to quit
+    fn exec(&mut self, line: &str) -> Result<bool, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (cmd, args) = match words.split_first() {
+            Some((cmd, args)) => (*cmd, args),
+            None => return Ok(true),
+        };
+
+        match cmd {
+            "s" | "step" => {
+                let n = opt_arg(args, 0, "count", 1)?;
+                self.resume(Some(n));
+            }
+            "c" | "continue" => self.resume(None),
+            "bs" | "back" => {
+                let n = opt_arg(args, 0, "count", 1)?;
+                let undone = (0..n).take_while(|_| self.computer.step_back()).count();
+                if undone < n {
+                    println!("history exhausted after {} instructions", undone);
+                }
+                self.list(self.computer.pc(), 1);
+            }
+            "rb" | "rback" => {
+                let addr = arg(args, 0, "addr")?;
+                if !self.computer.run_back_to(addr) {
+                    println!("history exhausted before reaching {}", addr);
+                }
+                self.list(self.computer.pc(), 1);
+            }
+            "b" | "break" => {
+                let addr = arg(args, 0, "addr")?;
+                self.computer.add_breakpoint(addr);
+            }
+            "d" | "delete" => {
+                let addr = arg(args, 0, "addr")?;
+                if !self.computer.remove_breakpoint(addr) {
+                    return Err(format!("no breakpoint at {}", addr));
+                }
+            }
+            "w" | "watch" => {
+                let addr = arg(args, 0, "addr")?;
+                self.computer.add_watchpoint(addr);
+            }
+            "u" | "unwatch" => {
+                let addr = arg(args, 0, "addr")?;
+                if !self.computer.remove_watchpoint(addr) {
+                    return Err(format!("no watchpoint at {}", addr));
+                }
+            }
+            "info" => {
+                let mut breakpoints: Vec<&usize> = self.computer.breakpoints().iter().collect();
+                breakpoints.sort();
+                let mut watchpoints: Vec<&usize> = self.computer.watchpoints().iter().collect();
+                watchpoints.sort();
+                println!("breakpoints: {:?}", breakpoints);
+                println!("watchpoints: {:?}", watchpoints);
+            }
+            "x" => {
+                let addr: usize = arg(args, 0, "addr")?;
+                let n = opt_arg(args, 1, "count", 1)?;
+                for a in addr..addr.saturating_add(n) {
+                    println!("{:04}: {}", a, self.computer.peek(a));
+                }
+            }
+            "poke" => {
+                let addr = arg(args, 0, "addr")?;
+                let value = arg(args, 1, "value")?;
+                self.computer.poke(addr, value).map_err(|e| e.to_string())?;
+            }
+            "l" | "list" => {
+                let addr = opt_arg(args, 0, "addr", self.computer.pc())?;
+                let n = opt_arg(args, 1, "count", 10)?;
+                self.list(addr, n);
+            }
+            "r" | "regs" => println!(
+                "pc: {}, base: {}, state: {:?}",
+                self.computer.pc(),
+                self.computer.base(),
+                self.computer.state
+            ),
+            "stats" => print!("{}", self.computer.stats()),
+            "pc" => {
+                let pc = arg(args, 0, "addr")?;
+                self.computer.set_pc(pc);
+            }
+            "base" => {
+                let base = arg(args, 0, "value")?;
+                self.computer.set_base(base);
+            }
+            "i" | "input" => {
+                for idx in 0..args.len() {
+                    let value = arg(args, idx, "value")?;
+                    self.computer.input.push_back(value);
+                }
+            }
+            "o" | "output" => {
+                let output: Vec<i64> = self.computer.output.drain(..).collect();
+                println!("{:?}", output);
+            }
+            "h" | "help" => println!("{}", HELP),
+            "q" | "quit" => return Ok(false),
+            _ => return Err(format!("unknown command '{}', try 'help'", cmd)),
+        }
+        Ok(true)
+    }
+
+    // Execute limit instructions, or until the program stops if there is no limit
+    fn resume(&mut self, limit: Option<usize>) {
+        let result = match limit {
+            None => self.computer.run(),
+            Some(n) => {
+                let mut result = Ok(ProgramState::Running);
+                for _ in 0..n {
+                    result = self.computer.step();
+                    if result != Ok(ProgramState::Running) {
+                        break;
+                    }
+                }
+                result
+            }
+        };
+
+        match result {
+            Err(e) => println!("fault: {}", e),
+            Ok(ProgramState::Running) => {}
+            Ok(ProgramState::Breakpoint(addr)) => println!("breakpoint at {}", addr),
+            Ok(ProgramState::Watchpoint(addr)) => {
+                println!("watchpoint {}: {}", addr, self.computer.peek(addr))
+            }
+            Ok(state) => println!("{:?}", state),
+        }
+        self.list(self.computer.pc(), 1);
+    }
+
+    fn list(&self, mut addr: usize, n: usize) {
+        for _ in 0..n {
+            if addr >= self.computer.program.len() {
+                break;
+            }
+            let marker = if addr == self.computer.pc() {
+                "=>"
+            } else {
+                "  "
+            };
+            match disasm::instruction_at(&self.computer.program, addr) {
+                Some(line) => {
+                    println!("{} {}", marker, line);
+                    addr += line.len();
+                }
+                None => {
+                    println!("{} {:04}: .data {}", marker, addr, self.computer.peek(addr));
+                    addr += 1;
+                }
+            }
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 2 {
+        eprintln!("Usage: {} <program>", args[0]);
+        process::exit(1);
+    }
+    let program = match load_program(&args[1]) {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("{}: {}", args[1], e);
+            process::exit(1);
+        }
+    };
+
+    let mut debugger = Debugger::new(program);
+    debugger.list(0, 1);
+
+    let stdin = io::stdin();
+    let mut last = String::new();
+    loop {
+        print!("(dbg) ");
+        io::stdout().flush().unwrap();
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap() == 0 {
+            break;
+        }
+        if line.trim().is_empty() {
+            line = last.clone();
+        }
+        match debugger.exec(&line) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => println!("error: {}", e),
+        }
+        last = line;
+    }
+}
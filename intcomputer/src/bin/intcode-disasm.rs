@@ -0,0 +1,28 @@
+use intcomputer::{cfg, disasm, load_program};
+use std::env;
+use std::process;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let (dot, path) = match args.len() {
+        2 => (false, &args[1]),
+        3 if args[1] == "--dot" => (true, &args[2]),
+        _ => {
+            eprintln!("Usage: {} [--dot] <program>", args[0]);
+            process::exit(1);
+        }
+    };
+    let program = match load_program(path) {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            process::exit(1);
+        }
+    };
+
+    if dot {
+        print!("{}", cfg::analyze(&program).to_dot());
+    } else {
+        print!("{}", disasm::disassemble(&program));
+    }
+}
@@ -0,0 +1,95 @@
+use intcomputer::trace::{self, TraceReader, TraceWriter};
+use intcomputer::{load_program, IntComputer, ProgramState};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::process;
+
+const USAGE: &str = "\
+Usage: intcode-trace record <program> <trace> [input...]
+       intcode-trace show <trace>
+       intcode-trace diff <trace> <trace>";
+
+fn open(path: &str) -> io::Result<TraceReader<BufReader<File>>> {
+    TraceReader::new(BufReader::new(File::open(path)?))
+}
+
+// Run the program on the given input and record every executed instruction
+fn record(program: &str, trace: &str, input: &[i64]) -> io::Result<()> {
+    let program = load_program(program)?;
+
+    let writer = TraceWriter::new(BufWriter::new(File::create(trace)?))?;
+    let mut comp = IntComputer::new(program);
+    comp.input.extend(input);
+    comp.add_observer(writer.clone());
+
+    match comp.run() {
+        Ok(ProgramState::Finished) => {}
+        Ok(state) => eprintln!("program stopped: {:?}", state),
+        Err(e) => eprintln!("program faulted: {}", e),
+    }
+    println!("{:?}", comp.output);
+    writer.finish()
+}
+
+// Input values given on the command line, exits with the usage on anything but integers
+fn parse_input(args: &[String]) -> Vec<i64> {
+    args.iter()
+        .map(|arg| arg.parse::<i64>().map_err(|_| arg))
+        .collect::<Result<_, _>>()
+        .unwrap_or_else(|arg| {
+            eprintln!("error: input value '{}' is not an integer", arg);
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        })
+}
+
+fn show(trace: &str) -> io::Result<()> {
+    for (index, entry) in open(trace)?.enumerate() {
+        println!("#{} {}", index, entry?);
+    }
+    Ok(())
+}
+
+fn diff(left: &str, right: &str) -> io::Result<bool> {
+    let divergence = match trace::diff(open(left)?, open(right)?)? {
+        Some(divergence) => divergence,
+        None => {
+            println!("traces are identical");
+            return Ok(true);
+        }
+    };
+
+    println!("traces diverge at instruction #{}", divergence.index);
+    let describe = |entry: Option<trace::TraceEntry>| match entry {
+        Some(entry) => entry.to_string(),
+        None => "<end of trace>".to_string(),
+    };
+    println!("< {}", describe(divergence.left));
+    println!("> {}", describe(divergence.right));
+    Ok(false)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let result = match args.get(1).map(|s| s.as_str()) {
+        Some("record") if args.len() >= 4 => {
+            record(&args[2], &args[3], &parse_input(&args[4..])).map(|_| true)
+        }
+        Some("show") if args.len() == 3 => show(&args[2]).map(|_| true),
+        Some("diff") if args.len() == 4 => diff(&args[2], &args[3]),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    match result {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(2);
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use crate::disasm::{self, decode};
+use crate::{Instruction, ParameterMode};
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+use std::ops::Range;
+
+// How control leaves a basic block
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Exit {
+    // Execution continues with the next block
+    Fallthrough(usize),
+    // Conditional or unconditional jump, target is None for computed jumps
+    Jump {
+        target: Option<usize>,
+        fallthrough: Option<usize>,
+    },
+    Halt,
+    // The next instruction can't be decoded
+    Invalid,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasicBlock {
+    // Addresses covered by the instructions of the block
+    pub range: Range<usize>,
+    // Start addresses of the instructions
+    pub instructions: Vec<usize>,
+    pub exit: Exit,
+}
+
+// Control-flow graph of the code reachable from address 0
+//
+// Built by the same traversal as the disassembler: immediate jump targets are followed,
+// computed jumps end the traversal and constants stored by `ADD #a, #b` or `MUL #a, #b` are
+// tried as return addresses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlFlowGraph {
+    // Blocks ordered by address
+    pub blocks: Vec<BasicBlock>,
+    // Immediate jump targets, except those in the middle of an instruction or past the end
+    pub jump_targets: BTreeSet<usize>,
+    // Maximal address ranges not covered by reachable instructions
+    pub data: Vec<Range<usize>>,
+    // Instructions writing to a fixed address inside reachable code or to a jump target
+    pub self_modifying: Vec<usize>,
+    // Instructions with relative parameters or adjusting the relative base
+    pub relative_base: Vec<usize>,
+    // Program the graph was built from, to list the instructions of the blocks
+    program: Vec<i64>,
+}
+
+// Graphviz DOT rendering of a control-flow graph, see ControlFlowGraph::to_dot
+pub struct Dot<'a> {
+    cfg: &'a ControlFlowGraph,
+}
+
+impl BasicBlock {
+    pub fn successors(&self) -> Vec<usize> {
+        match self.exit {
+            Exit::Fallthrough(next) => vec![next],
+            Exit::Jump {
+                target,
+                fallthrough,
+            } => target.into_iter().chain(fallthrough).collect(),
+            Exit::Halt | Exit::Invalid => vec![],
+        }
+    }
+}
+
+// Build the control-flow graph of a program
+pub fn analyze(program: &[i64]) -> ControlFlowGraph {
+    let (starts, labels) = disasm::find_code(program);
+    let mut covered = vec![false; program.len()];
+    let mut jump_targets = BTreeSet::new();
+    let mut self_modifying_candidates = vec![];
+    let mut relative_base = vec![];
+
+    let mut sorted: Vec<usize> = starts.iter().cloned().collect();
+    sorted.sort_unstable();
+    for &addr in sorted.iter() {
+        let (opcode, params) = decode(program, addr).unwrap();
+        for c in covered[addr..addr + opcode.instr.len()].iter_mut() {
+            *c = true;
+        }
+        let (mode1, mode2, mode3) = opcode.param_mode;
+        let modes = [mode1, mode2, mode3];
+        let modes = &modes[..params.len()];
+
+        if let Instruction::JumpT | Instruction::JumpF = opcode.instr {
+            // Targets are labelled by the disassembler unless they can't hold an instruction
+            if mode2 == ParameterMode::Immediate
+                && params[1] >= 0
+                && labels.contains(&(params[1] as usize))
+            {
+                jump_targets.insert(params[1] as usize);
+            }
+        }
+        if opcode.instr.writes() && modes[modes.len() - 1] == ParameterMode::Position {
+            self_modifying_candidates.push((addr, params[params.len() - 1]));
+        }
+        if opcode.instr == Instruction::AdjBase || modes.contains(&ParameterMode::Relative) {
+            relative_base.push(addr);
+        }
+    }
+    let self_modifying = self_modifying_candidates
+        .into_iter()
+        .filter(|&(_, dst)| {
+            dst >= 0
+                && (covered.get(dst as usize) == Some(&true)
+                    || jump_targets.contains(&(dst as usize)))
+        })
+        .map(|(addr, _)| addr)
+        .collect();
+
+    ControlFlowGraph {
+        blocks: blocks(program, &sorted, &starts, &labels),
+        jump_targets,
+        data: data_regions(&covered),
+        self_modifying,
+        relative_base,
+        program: program.to_vec(),
+    }
+}
+
+// Split the instructions into blocks, starting a new one at every label and after every jump
+fn blocks(
+    program: &[i64],
+    sorted: &[usize],
+    starts: &HashSet<usize>,
+    labels: &HashSet<usize>,
+) -> Vec<BasicBlock> {
+    let mut blocks = vec![];
+    let mut current: Option<BasicBlock> = None;
+    for &addr in sorted.iter() {
+        let (opcode, params) = decode(program, addr).unwrap();
+        let next = addr + opcode.instr.len();
+        let mut block = match current.take() {
+            Some(block) if !labels.contains(&addr) => block,
+            Some(block) => {
+                blocks.push(BasicBlock {
+                    exit: Exit::Fallthrough(addr),
+                    ..block
+                });
+                new_block(addr)
+            }
+            None => new_block(addr),
+        };
+        block.range.end = next;
+        block.instructions.push(addr);
+
+        let (mode1, mode2, _) = opcode.param_mode;
+        let fallthrough = Some(next).filter(|next| starts.contains(next));
+        let exit = match opcode.instr {
+            Instruction::Halt => Some(Exit::Halt),
+            Instruction::JumpT | Instruction::JumpF => {
+                let target = match mode2 {
+                    ParameterMode::Immediate if params[1] >= 0 => Some(params[1] as usize),
+                    _ => None,
+                };
+                // Conditions in immediate mode make the jump unconditional or a no-op
+                let taken = match mode1 {
+                    ParameterMode::Immediate => {
+                        Some((params[0] != 0) == (opcode.instr == Instruction::JumpT))
+                    }
+                    _ => None,
+                };
+                Some(match (taken, fallthrough) {
+                    (Some(true), _) => Exit::Jump {
+                        target,
+                        fallthrough: None,
+                    },
+                    (Some(false), Some(next)) => Exit::Fallthrough(next),
+                    (Some(false), None) => Exit::Invalid,
+                    (None, _) => Exit::Jump {
+                        target,
+                        fallthrough,
+                    },
+                })
+            }
+            _ if fallthrough.is_none() => Some(Exit::Invalid),
+            _ => None,
+        };
+        match exit {
+            Some(exit) => blocks.push(BasicBlock { exit, ..block }),
+            None => current = Some(block),
+        }
+    }
+    blocks
+}
+
+fn new_block(addr: usize) -> BasicBlock {
+    BasicBlock {
+        range: addr..addr,
+        instructions: vec![],
+        exit: Exit::Invalid,
+    }
+}
+
+fn data_regions(covered: &[bool]) -> Vec<Range<usize>> {
+    let mut regions = vec![];
+    let mut addr = 0;
+    while addr < covered.len() {
+        if covered[addr] {
+            addr += 1;
+            continue;
+        }
+        let start = addr;
+        while addr < covered.len() && !covered[addr] {
+            addr += 1;
+        }
+        regions.push(start..addr);
+    }
+    regions
+}
+
+impl ControlFlowGraph {
+    // Render the graph in Graphviz DOT format, one node per block listing its instructions
+    // Jumps to addresses without a reachable instruction, like the middle of an instruction,
+    // data or past the end of the program, lead to a separate unresolved node
+    pub fn to_dot(&self) -> Dot<'_> {
+        Dot { cfg: self }
+    }
+}
+
+impl fmt::Display for Dot<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cfg = self.cfg;
+        writeln!(f, "digraph intcode {{")?;
+        writeln!(f, "    node [shape=box fontname=monospace];")?;
+        for block in cfg.blocks.iter() {
+            let mut label = String::new();
+            for &addr in block.instructions.iter() {
+                if let Some(line) = disasm::instruction_at(&cfg.program, addr) {
+                    label.push_str(&line.to_string().replace('"', "\\\""));
+                    label.push_str("\\l");
+                }
+            }
+            let style = if block
+                .instructions
+                .iter()
+                .any(|addr| cfg.self_modifying.contains(addr))
+            {
+                " color=red"
+            } else {
+                ""
+            };
+            writeln!(
+                f,
+                "    b{} [label=\"{}\"{}];",
+                block.range.start, label, style
+            )?;
+        }
+        let mut unresolved = BTreeSet::new();
+        for block in cfg.blocks.iter() {
+            let edges = match block.exit {
+                Exit::Fallthrough(next) => vec![(Some(next), "")],
+                Exit::Jump {
+                    target,
+                    fallthrough,
+                } => vec![(target, " [label=jump]"), (fallthrough, " [style=dashed]")],
+                Exit::Halt | Exit::Invalid => vec![],
+            };
+            for (dst, attrs) in edges {
+                let dst = match dst {
+                    Some(dst) => dst,
+                    None => continue,
+                };
+                if cfg.blocks.iter().any(|b| b.range.start == dst) {
+                    writeln!(f, "    b{} -> b{}{};", block.range.start, dst, attrs)?;
+                } else {
+                    writeln!(
+                        f,
+                        "    b{} -> unresolved{}{};",
+                        block.range.start, dst, attrs
+                    )?;
+                    unresolved.insert(dst);
+                }
+            }
+            if let Exit::Jump { target: None, .. } = block.exit {
+                writeln!(
+                    f,
+                    "    b{} -> computed{};\n    computed{} [label=\"?\" shape=circle];",
+                    block.range.start, block.range.start, block.range.start
+                )?;
+            }
+        }
+        for dst in unresolved {
+            writeln!(
+                f,
+                "    unresolved{} [label=\"{:04}: ?\" shape=octagon color=red];",
+                dst, dst
+            )?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocks() {
+        // Count down from the input to 0, then halt
+        //   0: IN -> [12]
+        //   2: OUT [12]
+        //   4: ADD [12], #-1 -> [12]
+        //   8: JT [12], #2
+        //  11: HLT
+        //  12: .data 0
+        let program = vec![3, 12, 4, 12, 1001, 12, -1, 12, 1005, 12, 2, 99, 0];
+        let cfg = analyze(&program);
+        assert_eq!(
+            cfg.blocks,
+            vec![
+                BasicBlock {
+                    range: 0..2,
+                    instructions: vec![0],
+                    exit: Exit::Fallthrough(2),
+                },
+                BasicBlock {
+                    range: 2..11,
+                    instructions: vec![2, 4, 8],
+                    exit: Exit::Jump {
+                        target: Some(2),
+                        fallthrough: Some(11),
+                    },
+                },
+                BasicBlock {
+                    range: 11..12,
+                    instructions: vec![11],
+                    exit: Exit::Halt,
+                },
+            ]
+        );
+        assert_eq!(cfg.blocks[1].successors(), vec![2, 11]);
+        assert_eq!(cfg.jump_targets, vec![2].into_iter().collect());
+        assert_eq!(cfg.data, vec![12..13]);
+        assert!(cfg.self_modifying.is_empty());
+        assert!(cfg.relative_base.is_empty());
+    }
+
+    #[test]
+    fn test_properties() {
+        //   0: ADD #99, #0 -> [9]      rewrites the JT below
+        //   4: ARB #10
+        //   6: JT #1, #9
+        //   9: .data 0
+        let program = vec![1101, 99, 0, 9, 109, 10, 1105, 1, 9, 0];
+        let cfg = analyze(&program);
+        assert_eq!(cfg.self_modifying, vec![0]);
+        assert_eq!(cfg.relative_base, vec![4]);
+        assert_eq!(
+            cfg.blocks[0].exit,
+            Exit::Jump {
+                target: Some(9),
+                fallthrough: None
+            }
+        );
+        // Address 9 only holds an opcode once the program ran
+        assert_eq!(cfg.data, vec![9..10]);
+        assert!(cfg
+            .to_dot()
+            .to_string()
+            .contains("    b0 -> unresolved9 [label=jump];\n"));
+    }
+
+    #[test]
+    fn test_dot() {
+        let program = vec![
+            3, 15, 4, 15, 1001, 15, -1, 15, 1005, 15, 2, 2105, 1, 15, 99, 0,
+        ];
+        let dot = analyze(&program).to_dot().to_string();
+        assert!(dot.starts_with("digraph intcode {\n"));
+        assert!(dot.contains("    b0 [label=\"0000: IN -> [15]\\l\"];\n"));
+        assert!(dot.contains("    b0 -> b2;\n"));
+        assert!(dot.contains("    b2 -> b2 [label=jump];\n"));
+        assert!(dot.contains("    b2 -> b11 [style=dashed];\n"));
+        assert!(dot.contains("    b11 -> computed11;\n"));
+    }
+
+    #[test]
+    fn test_unresolved_jump_target() {
+        //   0: IN -> [10]
+        //   2: JT [10], #6             into the middle of the ADD
+        //   5: ADD #1, #1 -> [10]
+        //   9: HLT
+        //  10: .data 0
+        let program = vec![3, 10, 1005, 10, 6, 1101, 1, 1, 10, 99, 0];
+        let cfg = analyze(&program);
+        assert!(cfg.jump_targets.is_empty());
+        assert_eq!(
+            cfg.blocks[0].exit,
+            Exit::Jump {
+                target: Some(6),
+                fallthrough: Some(5)
+            }
+        );
+        let dot = cfg.to_dot().to_string();
+        assert!(dot.contains("    b0 -> unresolved6 [label=jump];\n"));
+        assert!(dot.contains("    unresolved6 [label=\"0006: ?\" shape=octagon color=red];\n"));
+        assert!(!dot.contains("-> b6"));
+    }
+}
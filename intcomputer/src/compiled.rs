@@ -0,0 +1,405 @@
+use crate::{
+    Dialect, Instruction, IntComputer, IntcodeError, Memory, OpCode, ParameterMode, ProgramState,
+    DECODE_CACHE_SIZE,
+};
+use std::ops::Range;
+use std::rc::Rc;
+
+// Decoded parameter with the addressing mode resolved where possible
+#[derive(Clone, Copy)]
+enum Param {
+    Immediate(i64),
+    Position(usize),
+    Relative(i64),
+}
+
+struct Context<'a, M> {
+    comp: &'a mut IntComputer<M>,
+    // Addresses occupied by the running block
+    code: Range<usize>,
+    // Set once the block overwrote one of its own instructions
+    modified: bool,
+}
+
+// Compiled instruction, returns whether execution continues with the next one in the block
+type Exec<M> = Box<dyn Fn(&mut Context<M>) -> Result<bool, IntcodeError>>;
+
+struct Op<M> {
+    instr: Instruction,
+    pc: usize,
+    next: usize,
+    exec: Exec<M>,
+}
+
+// Straight-line code ending with a jump, a halt or an instruction that couldn't be compiled
+struct Block<M> {
+    start: usize,
+    // Memory words the block was compiled from
+    words: Vec<i64>,
+    ops: Vec<Op<M>>,
+}
+
+// Execution engine compiling basic blocks into chains of closures
+//
+// Blocks are compiled when first entered and reused while memory still holds the words they
+// were compiled from. A block that overwrites its own code stops after the writing
+// instruction, blocks found modified when entered are left to the interpreter from then on.
+// Instructions that fault whatever values they read, e.g. on invalid parameter modes, aren't
+// compiled and fault in the interpreter. Faults depending on the values, negative addresses
+// and exceeding the memory limit, are reported by the compiled code with pc set to the
+// faulting instruction, like the interpreter does.
+pub struct CompiledEngine<M> {
+    blocks: Vec<Option<Rc<Block<M>>>>,
+    // Block starts that are always interpreted
+    interpreted: Vec<bool>,
+}
+
+fn param(mode: ParameterMode, value: i64) -> Option<Param> {
+    match mode {
+        ParameterMode::Immediate => Some(Param::Immediate(value)),
+        ParameterMode::Position if value >= 0 => Some(Param::Position(value as usize)),
+        ParameterMode::Relative => Some(Param::Relative(value)),
+        _ => None,
+    }
+}
+
+impl<'a, M: Memory> Context<'a, M> {
+    fn address(&self, param: Param) -> Result<usize, IntcodeError> {
+        match param {
+            Param::Position(addr) => Ok(addr),
+            Param::Relative(offset) => {
+                let address = self.comp.base.wrapping_add(offset);
+                if address < 0 {
+                    return Err(IntcodeError::NegativeAddress {
+                        pc: self.comp.pc,
+                        address,
+                    });
+                }
+                Ok(address as usize)
+            }
+            Param::Immediate(_) => unreachable!("immediate parameters have no address"),
+        }
+    }
+
+    fn load(&mut self, param: Param) -> Result<i64, IntcodeError> {
+        if let Param::Immediate(value) = param {
+            return Ok(value);
+        }
+        let addr = self.address(param)?;
+        self.comp.stats.access(addr);
+        Ok(self.comp.program.load(addr))
+    }
+
+    fn store(&mut self, param: Param, value: i64) -> Result<(), IntcodeError> {
+        let addr = self.address(param)?;
+        self.comp.store(addr, value)?;
+        self.comp.stats.access(addr);
+        if self.code.contains(&addr) {
+            self.modified = true;
+        }
+        Ok(())
+    }
+}
+
+impl<M: Memory + 'static> Default for CompiledEngine<M> {
+    fn default() -> Self {
+        Self {
+            blocks: vec![],
+            interpreted: vec![],
+        }
+    }
+}
+
+impl<M: Memory + 'static> CompiledEngine<M> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Equivalent of IntComputer::run
+    // Computers using debugging aids, a budget, history, checked arithmetic, a restricted dialect
+    // or custom instructions are run by the interpreter
+    pub fn run(&mut self, comp: &mut IntComputer<M>) -> Result<ProgramState, IntcodeError> {
+        if !comp.observers.is_empty()
+            || !comp.breakpoints.is_empty()
+            || !comp.watchpoints.is_empty()
+            || comp.budget.is_some()
+            || comp.history_limit.is_some()
+            || comp.checked
+            || comp.dialect != Dialect::Day9
+            || !comp.custom.is_empty()
+        {
+            return comp.run();
+        }
+
+        comp.state = ProgramState::Running;
+        while comp.state == ProgramState::Running {
+            match self.block(comp) {
+                Some(block) => Self::exec(&block, comp)?,
+                None => comp.exec_instr()?,
+            }
+        }
+        Ok(comp.state)
+    }
+
+    fn exec(block: &Block<M>, comp: &mut IntComputer<M>) -> Result<(), IntcodeError> {
+        let mut ctx = Context {
+            code: block.start..block.start + block.words.len(),
+            comp,
+            modified: false,
+        };
+        for op in block.ops.iter() {
+            ctx.comp.pc = op.pc;
+            let cont = (op.exec)(&mut ctx)?;
+            if ctx.comp.state != ProgramState::WaitingForInput {
+                ctx.comp.stats.record(op.instr);
+            }
+            if !cont {
+                return Ok(());
+            }
+            if ctx.modified {
+                ctx.comp.pc = op.next;
+                return Ok(());
+            }
+        }
+        ctx.comp.pc = ctx.code.end;
+        Ok(())
+    }
+
+    // Compiled block starting at the pc of comp, None if it has to be interpreted
+    fn block(&mut self, comp: &IntComputer<M>) -> Option<Rc<Block<M>>> {
+        let pc = comp.pc;
+        if pc >= DECODE_CACHE_SIZE || self.interpreted.get(pc) == Some(&true) {
+            return None;
+        }
+        if let Some(Some(block)) = self.blocks.get(pc) {
+            let unchanged = block
+                .words
+                .iter()
+                .enumerate()
+                .all(|(i, &word)| comp.program.load(pc + i) == word);
+            if unchanged {
+                return Some(Rc::clone(block));
+            }
+            // Self-modifying code, stop compiling it
+            self.blocks[pc] = None;
+            if pc >= self.interpreted.len() {
+                self.interpreted.resize(pc + 1, false);
+            }
+            self.interpreted[pc] = true;
+            return None;
+        }
+
+        let block = Rc::new(compile(comp, pc)?);
+        if pc >= self.blocks.len() {
+            self.blocks.resize_with(pc + 1, || None);
+        }
+        self.blocks[pc] = Some(Rc::clone(&block));
+        Some(block)
+    }
+}
+
+// Compile instructions starting at addr up to the end of the basic block, None if the first
+// instruction can't be compiled
+fn compile<M: Memory + 'static>(comp: &IntComputer<M>, start: usize) -> Option<Block<M>> {
+    let mut ops = vec![];
+    let mut addr = start;
+    loop {
+        let op = match compile_instr(comp, addr) {
+            Some(op) => op,
+            None => break,
+        };
+        addr = op.next;
+        let ends_block = matches!(
+            op.instr,
+            Instruction::JumpT | Instruction::JumpF | Instruction::Halt
+        );
+        ops.push(op);
+        if ends_block {
+            break;
+        }
+    }
+    if ops.is_empty() {
+        return None;
+    }
+    Some(Block {
+        start,
+        words: (start..addr).map(|a| comp.program.load(a)).collect(),
+        ops,
+    })
+}
+
+fn compile_instr<M: Memory + 'static>(comp: &IntComputer<M>, pc: usize) -> Option<Op<M>> {
+    let opcode = OpCode::new(comp.program.load(pc))?;
+    let next = pc + opcode.instr.len();
+    if next > comp.program.size() {
+        return None;
+    }
+    let (mode1, mode2, mode3) = opcode.param_mode;
+    let word = |offset: usize| comp.program.load(pc + offset);
+    let instr = opcode.instr;
+
+    let exec: Exec<M> = match instr {
+        Instruction::Add | Instruction::Multiply | Instruction::Less | Instruction::Equal => {
+            let a = param(mode1, word(1))?;
+            let b = param(mode2, word(2))?;
+            let dst = param(mode3, word(3))?;
+            if let Param::Immediate(_) = dst {
+                return None;
+            }
+            let f: fn(i64, i64) -> i64 = match instr {
+                Instruction::Add => i64::wrapping_add,
+                Instruction::Multiply => i64::wrapping_mul,
+                Instruction::Less => |a, b| (a < b) as i64,
+                _ => |a, b| (a == b) as i64,
+            };
+            Box::new(move |ctx| {
+                let value = f(ctx.load(a)?, ctx.load(b)?);
+                ctx.store(dst, value)?;
+                Ok(true)
+            })
+        }
+        Instruction::Input => {
+            let dst = param(mode1, word(1))?;
+            if let Param::Immediate(_) = dst {
+                return None;
+            }
+            Box::new(move |ctx| match ctx.comp.next_input() {
+                None => {
+                    ctx.comp.state = ProgramState::WaitingForInput;
+                    Ok(false)
+                }
+                Some(value) => {
+                    ctx.store(dst, value)?;
+                    ctx.comp.input.pop_front();
+                    Ok(true)
+                }
+            })
+        }
+        Instruction::Output => {
+            let src = param(mode1, word(1))?;
+            Box::new(move |ctx| {
+                let value = ctx.load(src)?;
+                ctx.comp.emit(value);
+                Ok(true)
+            })
+        }
+        Instruction::AdjBase => {
+            let src = param(mode1, word(1))?;
+            Box::new(move |ctx| {
+                ctx.comp.base = ctx.comp.base.wrapping_add(ctx.load(src)?);
+                Ok(true)
+            })
+        }
+        Instruction::JumpT | Instruction::JumpF => {
+            let cond = param(mode1, word(1))?;
+            let target = param(mode2, word(2))?;
+            let jump_if = instr == Instruction::JumpT;
+            Box::new(move |ctx| {
+                let value = ctx.load(cond)?;
+                let dst = ctx.load(target)?;
+                if (value != 0) == jump_if {
+                    if dst < 0 {
+                        return Err(IntcodeError::NegativeAddress {
+                            pc: ctx.comp.pc,
+                            address: dst,
+                        });
+                    }
+                    ctx.comp.pc = dst as usize;
+                } else {
+                    ctx.comp.pc = next;
+                }
+                Ok(false)
+            })
+        }
+        Instruction::Halt => Box::new(move |ctx| {
+            ctx.comp.state = ProgramState::Finished;
+            Ok(false)
+        }),
+    };
+    Some(Op {
+        instr,
+        pc,
+        next,
+        exec,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Vec<i64> {
+        crate::parse_program(input).unwrap()
+    }
+
+    // Run program on both engines, feeding inputs one batch per run, and compare the results
+    // and the complete state after every run
+    fn assert_same(program: Vec<i64>, inputs: &[&[i64]]) {
+        let mut interpreted = IntComputer::new(program.clone());
+        let mut compiled = IntComputer::new(program);
+        let mut engine = CompiledEngine::new();
+        for input in inputs {
+            interpreted.input.extend(input.iter());
+            compiled.input.extend(input.iter());
+            assert_eq!(engine.run(&mut compiled), interpreted.run());
+            assert_eq!(compiled.snapshot(), interpreted.snapshot());
+            assert_eq!(compiled.stats(), interpreted.stats());
+        }
+    }
+
+    #[test]
+    fn test_day_inputs() {
+        let mut gravity = parse(include_str!("../../aoc02/input"));
+        gravity[1] = 12;
+        gravity[2] = 2;
+        assert_same(gravity, &[&[]]);
+
+        let diagnostics = parse(include_str!("../../aoc05/input"));
+        assert_same(diagnostics.clone(), &[&[1]]);
+        assert_same(diagnostics, &[&[5]]);
+
+        let amplifier = parse(include_str!("../../aoc07/input"));
+        assert_same(amplifier, &[&[], &[5], &[0]]);
+
+        let boost = parse(include_str!("../../aoc09/input"));
+        assert_same(boost.clone(), &[&[1]]);
+        assert_same(boost, &[&[2]]);
+    }
+
+    #[test]
+    fn test_examples() {
+        // Quine
+        assert_same(
+            vec![
+                109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+            ],
+            &[&[]],
+        );
+        // Compare input with 8
+        let compare = vec![
+            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
+            0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
+            20, 1105, 1, 46, 98, 99,
+        ];
+        for input in 7..=9 {
+            assert_same(compare.clone(), &[&[input]]);
+        }
+    }
+
+    #[test]
+    fn test_self_modifying() {
+        // Output 7, then overwrite the output instruction with a halt and jump back to it
+        assert_same(vec![104, 7, 1101, 0, 99, 0, 1105, 1, 0], &[&[]]);
+        // Overwrite the next instruction of the same block
+        assert_same(vec![1101, 0, 99, 4, 104, 1, 99], &[&[]]);
+    }
+
+    #[test]
+    fn test_faults() {
+        // Unknown opcode, negative jump target, negative relative address, immediate write
+        assert_same(vec![1101, 1, 1, 5, 42, 0], &[&[]]);
+        assert_same(vec![1105, 1, -1], &[&[]]);
+        assert_same(vec![109, -5, 204, 0, 99], &[&[]]);
+        assert_same(vec![11101, 1, 1, 5, 99], &[&[]]);
+    }
+}
@@ -0,0 +1,252 @@
+use crate::{
+    exec, Instruction, IntComputer, IntcodeError, Memory, OpCode, ParameterMode, ProgramState,
+};
+use std::sync::{Arc, Mutex, PoisonError};
+
+// Instruction set a program was written for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dialect {
+    // ADD, MUL and HLT with position parameters
+    Day2,
+    // Adds I/O, jumps, comparisons and immediate parameters
+    Day5,
+    // Adds ARB and relative parameters
+    #[default]
+    Day9,
+}
+
+// Parameters of a custom instruction, resolved like those of the built-in instructions
+pub struct Operands<'a, M> {
+    comp: &'a mut IntComputer<M>,
+    modes: [ParameterMode; 3],
+    arity: usize,
+}
+
+// Semantics of a custom instruction, shared by clones of the computer
+// pc moves past the instruction unless the handler changed it or stopped the program
+pub(crate) type Handler<M> =
+    Arc<Mutex<dyn FnMut(&mut Operands<M>) -> Result<(), IntcodeError> + Send>>;
+
+pub(crate) struct CustomOpcode<M> {
+    arity: usize,
+    handler: Handler<M>,
+}
+
+impl<M> Clone for CustomOpcode<M> {
+    fn clone(&self) -> Self {
+        CustomOpcode {
+            arity: self.arity,
+            handler: Arc::clone(&self.handler),
+        }
+    }
+}
+
+impl Dialect {
+    pub fn supports(&self, instr: Instruction) -> bool {
+        match self {
+            Dialect::Day2 => matches!(
+                instr,
+                Instruction::Add | Instruction::Multiply | Instruction::Halt
+            ),
+            Dialect::Day5 => instr != Instruction::AdjBase,
+            Dialect::Day9 => true,
+        }
+    }
+
+    fn supports_mode(&self, mode: ParameterMode) -> bool {
+        match mode {
+            ParameterMode::Position | ParameterMode::Invalid => true,
+            ParameterMode::Immediate => *self != Dialect::Day2,
+            ParameterMode::Relative => *self == Dialect::Day9,
+        }
+    }
+
+    // Reject instructions and parameter modes the dialect doesn't know
+    // Invalid modes are left to the parameter access, like in the full instruction set
+    pub(crate) fn check(&self, opcode: &OpCode, pc: usize, value: i64) -> Result<(), IntcodeError> {
+        if !self.supports(opcode.instr) {
+            return Err(IntcodeError::UnknownOpcode { pc, value });
+        }
+        let (mode1, mode2, mode3) = opcode.param_mode;
+        let modes = [mode1, mode2, mode3];
+        if modes[..opcode.instr.len() - 1]
+            .iter()
+            .any(|&mode| !self.supports_mode(mode))
+        {
+            return Err(IntcodeError::InvalidParameterMode { pc, value });
+        }
+        Ok(())
+    }
+}
+
+impl<'a, M: Memory> Operands<'a, M> {
+    // Number of parameters of the instruction
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    // Value of parameter n, counting from 0
+    pub fn read(&mut self, n: usize) -> Result<i64, IntcodeError> {
+        assert!(n < self.arity, "parameter {} out of range", n);
+        exec::read(self.comp, n + 1, self.modes[n])
+    }
+
+    // Write to the location given by parameter n, counting from 0
+    pub fn write(&mut self, n: usize, value: i64) -> Result<(), IntcodeError> {
+        assert!(n < self.arity, "parameter {} out of range", n);
+        exec::write(self.comp, n + 1, self.modes[n], value)
+    }
+
+    // Computer executing the instruction, e.g. to emit output or jump
+    pub fn computer(&mut self) -> &mut IntComputer<M> {
+        self.comp
+    }
+}
+
+impl<M: Memory> IntComputer<M> {
+    // Reject opcodes and parameter modes outside of dialect
+    pub fn set_dialect(&mut self, dialect: Dialect) {
+        self.dialect = dialect;
+        self.decoded.clear();
+    }
+
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    // Execute handler for every instruction with the operation number code, which takes
+    // precedence over the built-in instruction set
+    // The parameter modes of the up to 3 parameters are taken from the opcode as usual
+    pub fn register_opcode<F>(&mut self, code: i64, arity: usize, handler: F)
+    where
+        F: FnMut(&mut Operands<M>) -> Result<(), IntcodeError> + Send + 'static,
+    {
+        assert!((0..100).contains(&code), "operation {} out of range", code);
+        assert!(arity <= 3, "custom instructions take at most 3 parameters");
+        self.custom.insert(
+            code,
+            CustomOpcode {
+                arity,
+                handler: Arc::new(Mutex::new(handler)),
+            },
+        );
+    }
+
+    // Returns whether a custom instruction was registered for code
+    pub fn unregister_opcode(&mut self, code: i64) -> bool {
+        self.custom.remove(&code).is_some()
+    }
+
+    // Execute the custom instruction at pc if one is registered for its operation number
+    // Returns false for built-in instructions
+    pub(crate) fn exec_custom(&mut self, value: i64) -> Result<bool, IntcodeError> {
+        let code = value % 100;
+        let custom = match self.custom.remove(&code) {
+            Some(custom) => custom,
+            None => return Ok(false),
+        };
+        let (mode1, mode2, mode3) = OpCode::modes(value);
+        let (pc, arity) = (self.pc, custom.arity);
+        let result = {
+            let mut handler = custom
+                .handler
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            (*handler)(&mut Operands {
+                comp: self,
+                modes: [mode1, mode2, mode3],
+                arity,
+            })
+        };
+        // The handler may have replaced itself
+        self.custom.entry(code).or_insert(custom);
+        result?;
+        if self.pc == pc && self.state == ProgramState::Running {
+            self.pc += arity + 1;
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Dialect, IntComputer, IntcodeError, ProgramState};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_dialects() {
+        let mut comp = IntComputer::new(vec![1, 0, 0, 0, 99]);
+        comp.set_dialect(Dialect::Day2);
+        assert_eq!(comp.run(), Ok(ProgramState::Finished));
+        assert_eq!(comp.program, vec![2, 0, 0, 0, 99]);
+
+        let mut comp = IntComputer::new(vec![3, 0, 99]);
+        comp.set_dialect(Dialect::Day2);
+        assert_eq!(
+            comp.run(),
+            Err(IntcodeError::UnknownOpcode { pc: 0, value: 3 })
+        );
+
+        let mut comp = IntComputer::new(vec![1101, 1, 2, 0, 99]);
+        comp.set_dialect(Dialect::Day2);
+        assert_eq!(
+            comp.run(),
+            Err(IntcodeError::InvalidParameterMode { pc: 0, value: 1101 })
+        );
+        comp.set_dialect(Dialect::Day5);
+        assert_eq!(comp.run(), Ok(ProgramState::Finished));
+
+        let mut comp = IntComputer::new(vec![109, 1, 99]);
+        comp.set_dialect(Dialect::Day5);
+        assert_eq!(
+            comp.run(),
+            Err(IntcodeError::UnknownOpcode { pc: 0, value: 109 })
+        );
+
+        let mut comp = IntComputer::new(vec![204, 0, 99]);
+        comp.set_dialect(Dialect::Day5);
+        assert_eq!(
+            comp.run(),
+            Err(IntcodeError::InvalidParameterMode { pc: 0, value: 204 })
+        );
+    }
+
+    #[test]
+    fn test_custom_opcodes() {
+        // 50: debug print of one parameter
+        // 51: syscall storing the sum of its first two parameters in the third
+        //  0: DBG #7
+        //  2: SYS #3, #4 -> [9]
+        //  6: DBG [9]
+        //  8: HLT
+        let printed = Arc::new(Mutex::new(vec![]));
+        let log = Arc::clone(&printed);
+        let mut comp = IntComputer::new(vec![150, 7, 1151, 3, 4, 9, 50, 9, 99, 0]);
+        comp.register_opcode(50, 1, move |ops| {
+            let value = ops.read(0)?;
+            log.lock().unwrap().push(value);
+            Ok(())
+        });
+        comp.register_opcode(51, 3, |ops| {
+            let sum = ops.read(0)? + ops.read(1)?;
+            ops.write(2, sum)
+        });
+        assert_eq!(comp.run(), Ok(ProgramState::Finished));
+        assert_eq!(*printed.lock().unwrap(), vec![7, 7]);
+        assert_eq!(comp.stats().instructions, 4);
+
+        // Custom instructions override built-in ones and may jump
+        let mut comp = IntComputer::new(vec![104, 1, 4, 0, 99]);
+        comp.register_opcode(4, 1, |ops| {
+            let target = ops.read(0)?;
+            ops.computer().set_pc(target as usize + 3);
+            Ok(())
+        });
+        assert_eq!(comp.run(), Ok(ProgramState::Finished));
+        assert!(comp.output.is_empty());
+        assert!(comp.unregister_opcode(4));
+        comp.set_pc(0);
+        comp.run().unwrap();
+        assert_eq!(comp.output, vec![1, 104]);
+    }
+}
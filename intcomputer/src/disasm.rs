@@ -0,0 +1,317 @@
+use crate::{Instruction, OpCode, ParameterMode};
+use std::collections::HashSet;
+use std::fmt;
+
+const DATA_PER_LINE: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operand {
+    Immediate(i64),
+    Position(i64),
+    Relative(i64),
+    Label(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineKind {
+    Instruction {
+        mnemonic: &'static str,
+        operands: Vec<Operand>,
+        target: Option<Operand>,
+    },
+    Data(Vec<i64>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub addr: usize,
+    pub label: bool,
+    pub kind: LineKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Listing {
+    pub lines: Vec<Line>,
+}
+
+// Decode the instruction at addr if it is complete and uses valid parameter modes
+pub(crate) fn decode(program: &[i64], addr: usize) -> Option<(OpCode, &[i64])> {
+    let opcode = OpCode::new(*program.get(addr)?)?;
+    let len = opcode.instr.len();
+    let params = program.get(addr + 1..addr + len)?;
+
+    let (mode1, mode2, mode3) = opcode.param_mode;
+    let modes = [mode1, mode2, mode3];
+    for (i, &mode) in modes.iter().take(len - 1).enumerate() {
+        if mode == ParameterMode::Invalid {
+            return None;
+        }
+        if mode == ParameterMode::Immediate && opcode.instr.writes() && i == len - 2 {
+            return None;
+        }
+    }
+    Some((opcode, params))
+}
+
+// Find instruction start addresses and jump targets by following the control flow from
+// address 0. Immediate jump targets are followed, computed jumps are not. Constants stored
+// with `ADD #a, #b` or `MUL #a, #b` are treated as possible return addresses once the direct
+// flow is exhausted.
+pub(crate) fn find_code(program: &[i64]) -> (HashSet<usize>, HashSet<usize>) {
+    let mut starts = HashSet::new();
+    let mut labels = HashSet::new();
+    let mut covered = vec![false; program.len()];
+    let mut pending = vec![0];
+    let mut pointers = vec![];
+
+    loop {
+        let (addr, pointer) = match pending.pop() {
+            Some(addr) => (addr, false),
+            None => match pointers.pop() {
+                Some(addr) => (addr, true),
+                None => break,
+            },
+        };
+        if addr >= program.len() || covered[addr] {
+            continue;
+        }
+        let (opcode, params) = match decode(program, addr) {
+            Some(decoded) => decoded,
+            None => continue,
+        };
+        let next = addr + opcode.instr.len();
+        if covered[addr..next].iter().any(|&c| c) {
+            continue;
+        }
+        for c in covered[addr..next].iter_mut() {
+            *c = true;
+        }
+        starts.insert(addr);
+        if pointer {
+            labels.insert(addr);
+        }
+
+        let (mode1, mode2, _) = opcode.param_mode;
+        match opcode.instr {
+            Instruction::Halt => {}
+            Instruction::JumpT | Instruction::JumpF => {
+                if mode2 == ParameterMode::Immediate && params[1] >= 0 {
+                    labels.insert(params[1] as usize);
+                    pending.push(params[1] as usize);
+                }
+                // Conditions in immediate mode make the jump unconditional or a no-op
+                let always = match mode1 {
+                    ParameterMode::Immediate => {
+                        (params[0] != 0) == (opcode.instr == Instruction::JumpT)
+                    }
+                    _ => false,
+                };
+                if !always {
+                    pending.push(next);
+                }
+            }
+            Instruction::Add | Instruction::Multiply => {
+                if mode1 == ParameterMode::Immediate && mode2 == ParameterMode::Immediate {
+                    let value = match opcode.instr {
+                        Instruction::Add => params[0].wrapping_add(params[1]),
+                        _ => params[0].wrapping_mul(params[1]),
+                    };
+                    if value >= 0 && (value as usize) < program.len() {
+                        pointers.push(value as usize);
+                    }
+                }
+                pending.push(next);
+            }
+            _ => pending.push(next),
+        }
+    }
+
+    // Jump targets in the middle of an instruction or beyond the program can't be labelled
+    labels.retain(|addr| starts.contains(addr) || (*addr < program.len() && !covered[*addr]));
+    (starts, labels)
+}
+
+// Build the listing line for the instruction at addr, jump targets in labels are shown by name
+fn decode_line(program: &[i64], addr: usize, labels: &HashSet<usize>) -> Option<Line> {
+    let (opcode, params) = decode(program, addr)?;
+    let (mode1, mode2, mode3) = opcode.param_mode;
+    let mut operands: Vec<Operand> = [mode1, mode2, mode3]
+        .iter()
+        .zip(params)
+        .map(|(&mode, &val)| match mode {
+            ParameterMode::Immediate => Operand::Immediate(val),
+            ParameterMode::Relative => Operand::Relative(val),
+            _ => Operand::Position(val),
+        })
+        .collect();
+
+    if let (Instruction::JumpT, Some(Operand::Immediate(dst)))
+    | (Instruction::JumpF, Some(Operand::Immediate(dst))) =
+        (opcode.instr, operands.get(1).cloned())
+    {
+        if dst >= 0 && labels.contains(&(dst as usize)) {
+            operands[1] = Operand::Label(dst as usize);
+        }
+    }
+    let target = if opcode.instr.writes() {
+        operands.pop()
+    } else {
+        None
+    };
+
+    Some(Line {
+        addr,
+        label: labels.contains(&addr),
+        kind: LineKind::Instruction {
+            mnemonic: opcode.instr.mnemonic(),
+            operands,
+            target,
+        },
+    })
+}
+
+// Decode the single instruction at addr regardless of reachability
+pub fn instruction_at(program: &[i64], addr: usize) -> Option<Line> {
+    decode_line(program, addr, &HashSet::new())
+}
+
+// Turn a program into a listing, separating reachable code from data
+pub fn disassemble(program: &[i64]) -> Listing {
+    let (starts, labels) = find_code(program);
+    let mut lines = vec![];
+    let mut addr = 0;
+
+    while addr < program.len() {
+        if starts.contains(&addr) {
+            let line = decode_line(program, addr, &labels).unwrap();
+            addr += line.len();
+            lines.push(line);
+        } else {
+            // Data runs end at the next instruction, label or after DATA_PER_LINE values
+            let mut end = addr + 1;
+            while end < program.len()
+                && end - addr < DATA_PER_LINE
+                && !starts.contains(&end)
+                && !labels.contains(&end)
+            {
+                end += 1;
+            }
+            lines.push(Line {
+                addr,
+                label: labels.contains(&addr),
+                kind: LineKind::Data(program[addr..end].to_vec()),
+            });
+            addr = end;
+        }
+    }
+
+    Listing { lines }
+}
+
+impl Line {
+    // Number of memory cells covered by the line
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        match &self.kind {
+            LineKind::Instruction {
+                operands, target, ..
+            } => 1 + operands.len() + target.iter().count(),
+            LineKind::Data(values) => values.len(),
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operand::Immediate(val) => write!(f, "#{}", val),
+            Operand::Position(addr) => write!(f, "[{}]", addr),
+            Operand::Relative(offset) if *offset < 0 => write!(f, "[r{}]", offset),
+            Operand::Relative(offset) => write!(f, "[r+{}]", offset),
+            Operand::Label(addr) => write!(f, "L{:04}", addr),
+        }
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.label {
+            writeln!(f, "L{:04}:", self.addr)?;
+        }
+        write!(f, "{:04}: ", self.addr)?;
+        match &self.kind {
+            LineKind::Instruction {
+                mnemonic,
+                operands,
+                target,
+            } => {
+                write!(f, "{}", mnemonic)?;
+                for (i, operand) in operands.iter().enumerate() {
+                    let sep = if i == 0 { " " } else { ", " };
+                    write!(f, "{}{}", sep, operand)?;
+                }
+                if let Some(target) = target {
+                    write!(f, " -> {}", target)?;
+                }
+            }
+            LineKind::Data(values) => {
+                let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                write!(f, ".data {}", values.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Listing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let listing = disassemble(&[21101, 3, 5, 100, 204, -2, 99]);
+        assert_eq!(
+            listing.to_string(),
+            "0000: ADD #3, #5 -> [r+100]\n0004: OUT [r-2]\n0006: HLT\n"
+        );
+    }
+
+    #[test]
+    fn test_labels_and_data() {
+        // Unconditional jump over a data region, the skipped cells are never executed
+        let program = vec![1105, 1, 6, 42, 43, 44, 3, 3, 99];
+        let listing = disassemble(&program);
+        assert_eq!(
+            listing.to_string(),
+            "0000: JT #1, L0006\n0003: .data 42, 43, 44\nL0006:\n0006: IN -> [3]\n0008: HLT\n"
+        );
+    }
+
+    #[test]
+    fn test_return_address() {
+        // Store a return address, call a subroutine which jumps back through it
+        let program = vec![21101, 7, 0, 0, 1105, 1, 9, 99, 0, 2106, 0, 0];
+        let listing = disassemble(&program);
+        assert_eq!(
+            listing.to_string(),
+            "0000: ADD #7, #0 -> [r+0]\n0004: JT #1, L0009\nL0007:\n0007: HLT\n0008: .data 0\n\
+             L0009:\n0009: JF #0, [r+0]\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid_opcode_is_data() {
+        let listing = disassemble(&[1, 0, 0, 0, 12345, 7]);
+        assert_eq!(listing.lines.len(), 2);
+        assert_eq!(listing.lines[1].kind, LineKind::Data(vec![12345, 7]));
+    }
+}
@@ -0,0 +1,75 @@
+use std::error::Error;
+use std::fmt;
+
+// Faults raised while executing an Intcode program
+#[derive(Debug, Clone, PartialEq)]
+pub enum IntcodeError {
+    UnknownOpcode {
+        pc: usize,
+        value: i64,
+    },
+    InvalidParameterMode {
+        pc: usize,
+        value: i64,
+    },
+    ImmediateWrite {
+        pc: usize,
+    },
+    NegativeAddress {
+        pc: usize,
+        address: i64,
+    },
+    PcOutOfBounds {
+        pc: usize,
+    },
+    // Parameters of the instruction at pc lie past the end of memory
+    TruncatedInstruction {
+        pc: usize,
+    },
+    MemoryLimitExceeded {
+        pc: usize,
+        address: usize,
+        limit: usize,
+    },
+    // Result of an arithmetic instruction or address computation doesn't fit the value type
+    Overflow {
+        pc: usize,
+    },
+}
+
+impl fmt::Display for IntcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntcodeError::UnknownOpcode { pc, value } => {
+                write!(f, "unknown opcode {} at address {}", value, pc)
+            }
+            IntcodeError::InvalidParameterMode { pc, value } => {
+                write!(f, "invalid parameter mode in {} at address {}", value, pc)
+            }
+            IntcodeError::ImmediateWrite { pc } => {
+                write!(f, "write in immediate mode at address {}", pc)
+            }
+            IntcodeError::NegativeAddress { pc, address } => {
+                write!(
+                    f,
+                    "access to negative address {} at address {}",
+                    address, pc
+                )
+            }
+            IntcodeError::PcOutOfBounds { pc } => {
+                write!(f, "program counter {} is out of bounds", pc)
+            }
+            IntcodeError::TruncatedInstruction { pc } => {
+                write!(f, "instruction at address {} is truncated", pc)
+            }
+            IntcodeError::MemoryLimitExceeded { pc, address, limit } => write!(
+                f,
+                "write to address {} at address {} exceeds the memory limit of {} cells",
+                address, pc, limit
+            ),
+            IntcodeError::Overflow { pc } => write!(f, "integer overflow at address {}", pc),
+        }
+    }
+}
+
+impl Error for IntcodeError {}
@@ -0,0 +1,194 @@
+use crate::{Dialect, Instruction, IntcodeError, OpCode, ParameterMode, ProgramState};
+use std::convert::TryFrom;
+
+// Value held by memory cells, registers and the I/O queues of an interpreter
+pub(crate) trait Word: Clone + PartialOrd {
+    fn from_bool(value: bool) -> Self;
+    fn is_zero(&self) -> bool;
+    fn is_negative(&self) -> bool;
+    // None if the value doesn't fit
+    fn to_i64(&self) -> Option<i64>;
+    fn to_usize(&self) -> Option<usize>;
+}
+
+// State of an interpreter that instructions act on
+// The instruction set itself is implemented once by execute, interpreters only decide how
+// values are stored and add their own bookkeeping to memory accesses and I/O
+pub(crate) trait Machine {
+    type Word: Word;
+
+    fn pc(&self) -> usize;
+    fn set_pc(&mut self, pc: usize);
+    fn base(&self) -> Self::Word;
+    fn set_base(&mut self, base: Self::Word);
+    fn set_state(&mut self, state: ProgramState);
+    // Word at addr if it lies within the written memory
+    fn word(&self, addr: usize) -> Option<Self::Word>;
+    // Memory accesses of instruction parameters, cells beyond the end read as 0
+    fn read_cell(&mut self, addr: usize) -> Self::Word;
+    fn write_cell(&mut self, addr: usize, value: Self::Word) -> Result<(), IntcodeError>;
+    fn add(&self, a: Self::Word, b: Self::Word) -> Result<Self::Word, IntcodeError>;
+    fn mul(&self, a: Self::Word, b: Self::Word) -> Result<Self::Word, IntcodeError>;
+    // Next input value, which is only consumed once it has been written
+    fn peek_input(&mut self) -> Option<Self::Word>;
+    fn consume_input(&mut self);
+    fn emit_output(&mut self, value: Self::Word);
+    // Called with the value of every parameter read
+    fn operand(&mut self, _value: &Self::Word) {}
+}
+
+impl Word for i64 {
+    fn from_bool(value: bool) -> i64 {
+        value as i64
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == 0
+    }
+
+    fn is_negative(&self) -> bool {
+        *self < 0
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        Some(*self)
+    }
+
+    fn to_usize(&self) -> Option<usize> {
+        usize::try_from(*self).ok()
+    }
+}
+
+// Opcode word at pc, which has to lie within the written memory and fit an i64
+pub(crate) fn fetch<M: Machine>(m: &M) -> Result<i64, IntcodeError> {
+    let pc = m.pc();
+    m.word(pc)
+        .ok_or(IntcodeError::PcOutOfBounds { pc })?
+        .to_i64()
+        .ok_or(IntcodeError::Overflow { pc })
+}
+
+// Decode the opcode word at pc, rejecting instructions outside of dialect
+pub(crate) fn decode(value: i64, pc: usize, dialect: &Dialect) -> Result<OpCode, IntcodeError> {
+    let opcode = OpCode::new(value).ok_or(IntcodeError::UnknownOpcode { pc, value })?;
+    dialect.check(&opcode, pc, value)?;
+    Ok(opcode)
+}
+
+// Execute the decoded instruction at pc
+// An input instruction without input sets the state to WaitingForInput and leaves pc alone
+pub(crate) fn execute<M: Machine>(m: &mut M, opcode: OpCode) -> Result<(), IntcodeError> {
+    let (mode1, mode2, mode3) = opcode.param_mode;
+    match opcode.instr {
+        Instruction::Add | Instruction::Multiply | Instruction::Less | Instruction::Equal => {
+            let op1 = read(m, 1, mode1)?;
+            let op2 = read(m, 2, mode2)?;
+            let result = match opcode.instr {
+                Instruction::Add => m.add(op1, op2)?,
+                Instruction::Multiply => m.mul(op1, op2)?,
+                Instruction::Less => M::Word::from_bool(op1 < op2),
+                _ => M::Word::from_bool(op1 == op2),
+            };
+            write(m, 3, mode3, result)?;
+        }
+        Instruction::Input => match m.peek_input() {
+            None => {
+                m.set_state(ProgramState::WaitingForInput);
+                return Ok(());
+            }
+            Some(input) => {
+                // Only consume the value once the write is known to succeed
+                write(m, 1, mode1, input)?;
+                m.consume_input();
+            }
+        },
+        Instruction::Output => {
+            let output = read(m, 1, mode1)?;
+            m.emit_output(output);
+        }
+        Instruction::JumpT | Instruction::JumpF => {
+            let val = read(m, 1, mode1)?;
+            let dst = read(m, 2, mode2)?;
+            if val.is_zero() == (opcode.instr == Instruction::JumpF) {
+                let dst = to_address(m, dst)?;
+                m.set_pc(dst);
+                return Ok(());
+            }
+        }
+        Instruction::AdjBase => {
+            let val = read(m, 1, mode1)?;
+            let base = m.add(m.base(), val)?;
+            m.set_base(base);
+        }
+        Instruction::Halt => {
+            m.set_state(ProgramState::Finished);
+            return Ok(());
+        }
+    }
+    m.set_pc(m.pc() + opcode.instr.len());
+    Ok(())
+}
+
+// Read from offset relative to pc according to the parameter mode
+pub(crate) fn read<M: Machine>(
+    m: &mut M,
+    offset: usize,
+    mode: ParameterMode,
+) -> Result<M::Word, IntcodeError> {
+    let value = match mode {
+        ParameterMode::Immediate => param(m, offset)?,
+        _ => {
+            let idx = address(m, offset, mode)?;
+            m.read_cell(idx)
+        }
+    };
+    m.operand(&value);
+    Ok(value)
+}
+
+// Write to the memory given by the parameter at offset from pc
+pub(crate) fn write<M: Machine>(
+    m: &mut M,
+    offset: usize,
+    mode: ParameterMode,
+    value: M::Word,
+) -> Result<(), IntcodeError> {
+    let idx = match mode {
+        ParameterMode::Immediate => return Err(IntcodeError::ImmediateWrite { pc: m.pc() }),
+        _ => address(m, offset, mode)?,
+    };
+    m.write_cell(idx, value)
+}
+
+// Resolve the memory address referenced by the parameter at offset
+fn address<M: Machine>(m: &M, offset: usize, mode: ParameterMode) -> Result<usize, IntcodeError> {
+    let val = param(m, offset)?;
+    let address = match mode {
+        ParameterMode::Position => val,
+        ParameterMode::Relative => m.add(m.base(), val)?,
+        _ => {
+            return Err(IntcodeError::InvalidParameterMode {
+                pc: m.pc(),
+                value: fetch(m).unwrap_or_default(),
+            })
+        }
+    };
+    to_address(m, address)
+}
+
+fn to_address<M: Machine>(m: &M, address: M::Word) -> Result<usize, IntcodeError> {
+    let pc = m.pc();
+    if address.is_negative() {
+        return Err(match address.to_i64() {
+            Some(address) => IntcodeError::NegativeAddress { pc, address },
+            None => IntcodeError::Overflow { pc },
+        });
+    }
+    address.to_usize().ok_or(IntcodeError::Overflow { pc })
+}
+
+// Parameter word at offset from pc, which has to lie within the written memory
+fn param<M: Machine>(m: &M, offset: usize) -> Result<M::Word, IntcodeError> {
+    m.word(m.pc() + offset)
+        .ok_or(IntcodeError::TruncatedInstruction { pc: m.pc() })
+}
@@ -0,0 +1,411 @@
+use crate::{IntComputer, IntOutput, IntcodeError, Memory, ProgramState};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::{self, Future};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+
+// Instructions run_async executes before letting other tasks run
+const YIELD_INTERVAL: u64 = 1024;
+
+// Source of input values for IntComputer::run_async, Ready(None) once no more input arrives
+pub trait AsyncIntInput {
+    fn poll_read(&mut self, cx: &mut Context) -> Poll<Option<i64>>;
+}
+
+// Destination for output values of IntComputer::run_async
+pub trait AsyncIntOutput {
+    fn poll_write(&mut self, cx: &mut Context, value: i64) -> Poll<()>;
+}
+
+struct Channel {
+    queue: VecDeque<i64>,
+    senders: usize,
+    waker: Option<Waker>,
+}
+
+// Sending half of an unbounded single threaded channel
+pub struct Sender {
+    channel: Rc<RefCell<Channel>>,
+}
+
+// Receiving half of an unbounded single threaded channel
+pub struct Receiver {
+    channel: Rc<RefCell<Channel>>,
+}
+
+pub fn channel() -> (Sender, Receiver) {
+    let channel = Rc::new(RefCell::new(Channel {
+        queue: VecDeque::new(),
+        senders: 1,
+        waker: None,
+    }));
+    (
+        Sender {
+            channel: Rc::clone(&channel),
+        },
+        Receiver { channel },
+    )
+}
+
+impl Sender {
+    pub fn send(&self, value: i64) {
+        let mut channel = self.channel.borrow_mut();
+        channel.queue.push_back(value);
+        if let Some(waker) = channel.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Clone for Sender {
+    fn clone(&self) -> Self {
+        self.channel.borrow_mut().senders += 1;
+        Self {
+            channel: Rc::clone(&self.channel),
+        }
+    }
+}
+
+// The receiver learns about the last sender going away
+impl Drop for Sender {
+    fn drop(&mut self) {
+        let mut channel = self.channel.borrow_mut();
+        channel.senders -= 1;
+        if channel.senders == 0 {
+            if let Some(waker) = channel.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl Receiver {
+    // Wait for the next value, None once all senders are gone and the channel is empty
+    pub async fn recv(&mut self) -> Option<i64> {
+        future::poll_fn(|cx| self.poll_read(cx)).await
+    }
+}
+
+impl AsyncIntInput for Receiver {
+    fn poll_read(&mut self, cx: &mut Context) -> Poll<Option<i64>> {
+        let mut channel = self.channel.borrow_mut();
+        match channel.queue.pop_front() {
+            Some(value) => Poll::Ready(Some(value)),
+            None if channel.senders == 0 => Poll::Ready(None),
+            None => {
+                channel.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl AsyncIntOutput for Sender {
+    fn poll_write(&mut self, _cx: &mut Context, value: i64) -> Poll<()> {
+        self.send(value);
+        Poll::Ready(())
+    }
+}
+
+// Synchronous outputs never make the computer wait
+impl<O: IntOutput> AsyncIntOutput for O {
+    fn poll_write(&mut self, _cx: &mut Context, value: i64) -> Poll<()> {
+        self.write(value);
+        Poll::Ready(())
+    }
+}
+
+impl<M: Memory> IntComputer<M> {
+    // Run the program, awaiting input from the source whenever the input queue is empty
+    // Every output is awaited on the sink before the next instruction executes, and other
+    // tasks get to run every YIELD_INTERVAL instructions
+    // Stops at breakpoints like run, returns WaitingForInput once the source has no more input
+    pub async fn run_async<I, O>(
+        &mut self,
+        input: &mut I,
+        output: &mut O,
+    ) -> Result<ProgramState, IntcodeError>
+    where
+        I: AsyncIntInput,
+        O: AsyncIntOutput,
+    {
+        let mut executed = 0u64;
+        loop {
+            if !self.resuming() && self.at_breakpoint() {
+                self.state = ProgramState::Breakpoint(self.pc);
+                return Ok(self.state);
+            }
+            let state = self.step()?;
+            while let Some(value) = self.output.pop_front() {
+                future::poll_fn(|cx| output.poll_write(cx, value)).await;
+            }
+            match state {
+                ProgramState::Running => {}
+                ProgramState::WaitingForInput => {
+                    match future::poll_fn(|cx| input.poll_read(cx)).await {
+                        Some(value) => self.input.push_back(value),
+                        None => return Ok(state),
+                    }
+                }
+                _ => return Ok(state),
+            }
+            executed += 1;
+            if executed.is_multiple_of(YIELD_INTERVAL) {
+                yield_now().await;
+            }
+        }
+    }
+}
+
+// Let the other tasks of the executor run before continuing
+fn yield_now() -> impl Future<Output = ()> {
+    let mut yielded = false;
+    future::poll_fn(move |cx| {
+        if yielded {
+            return Poll::Ready(());
+        }
+        yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    })
+}
+
+struct TaskWaker {
+    id: usize,
+    ready: Arc<Mutex<VecDeque<usize>>>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.ready.lock().unwrap().push_back(self.id);
+    }
+}
+
+// Single threaded executor polling its tasks until none of them can make progress
+// Tasks may borrow from the surrounding scope, e.g. computers owned by the caller
+#[derive(Default)]
+pub struct LocalExecutor<'a> {
+    tasks: Vec<Option<Pin<Box<dyn Future<Output = ()> + 'a>>>>,
+    ready: Arc<Mutex<VecDeque<usize>>>,
+}
+
+impl<'a> LocalExecutor<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn spawn<F: Future<Output = ()> + 'a>(&mut self, task: F) {
+        self.ready.lock().unwrap().push_back(self.tasks.len());
+        self.tasks.push(Some(Box::pin(task)));
+    }
+
+    // Poll woken tasks until all of them completed or the remaining ones wait for each other
+    // Returns the number of tasks that didn't complete
+    pub fn run(&mut self) -> usize {
+        loop {
+            let id = match self.ready.lock().unwrap().pop_front() {
+                Some(id) => id,
+                None => break,
+            };
+            let task = match self.tasks[id].as_mut() {
+                Some(task) => task,
+                None => continue,
+            };
+            let waker = Waker::from(Arc::new(TaskWaker {
+                id,
+                ready: Arc::clone(&self.ready),
+            }));
+            if task
+                .as_mut()
+                .poll(&mut Context::from_waker(&waker))
+                .is_ready()
+            {
+                self.tasks[id] = None;
+            }
+        }
+        self.tasks.iter().filter(|task| task.is_some()).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{COUNTDOWN, ECHO, FEEDBACK};
+    use crate::MemoryAccess;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Output queue holding at most one value
+    #[derive(Clone, Default)]
+    struct Bounded {
+        slot: Rc<RefCell<(Option<i64>, Option<Waker>)>>,
+    }
+
+    impl AsyncIntOutput for Bounded {
+        fn poll_write(&mut self, cx: &mut Context, value: i64) -> Poll<()> {
+            let mut slot = self.slot.borrow_mut();
+            if slot.0.is_some() {
+                slot.1 = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+            slot.0 = Some(value);
+            Poll::Ready(())
+        }
+    }
+
+    impl Bounded {
+        fn take(&self) -> Option<i64> {
+            let mut slot = self.slot.borrow_mut();
+            if let Some(waker) = slot.1.take() {
+                waker.wake();
+            }
+            slot.0.take()
+        }
+    }
+
+    #[test]
+    fn test_controller() {
+        let mut comp = IntComputer::new(ECHO.to_vec());
+        let (to_comp, mut comp_rx) = channel();
+        let (mut comp_tx, mut from_comp) = channel();
+        let mut received = vec![];
+
+        let mut executor = LocalExecutor::new();
+        executor.spawn(async move {
+            let state = comp.run_async(&mut comp_rx, &mut comp_tx).await;
+            assert_eq!(state, Ok(ProgramState::Finished));
+        });
+        executor.spawn(async {
+            // Count down, answering every echo with the next value
+            to_comp.send(3);
+            while let Some(value) = from_comp.recv().await {
+                received.push(value);
+                to_comp.send(value - 1);
+            }
+        });
+        assert_eq!(executor.run(), 0);
+        drop(executor);
+        assert_eq!(received, vec![3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn test_feedback_loop() {
+        let (first, mut rx) = channel();
+        let mut executor = LocalExecutor::new();
+        for &phase in [9, 8, 7, 6, 5].iter() {
+            let (mut tx, next_rx) = channel();
+            let mut comp = IntComputer::new(FEEDBACK.to_vec());
+            comp.input.push_back(phase);
+            let mut input = rx;
+            executor.spawn(async move {
+                comp.run_async(&mut input, &mut tx).await.unwrap();
+            });
+            rx = next_rx;
+        }
+
+        let mut last = 0;
+        first.send(0);
+        executor.spawn(async {
+            while let Some(value) = rx.recv().await {
+                last = value;
+                first.send(value);
+            }
+        });
+        assert_eq!(executor.run(), 0);
+        drop(executor);
+        assert_eq!(last, 139629729);
+    }
+
+    #[test]
+    fn test_stalled() {
+        let mut comp = IntComputer::new(vec![3, 0, 99]);
+        let (_tx, mut rx) = channel();
+        let mut executor = LocalExecutor::new();
+        executor.spawn(async {
+            comp.run_async(&mut rx, &mut vec![]).await.unwrap();
+        });
+        assert_eq!(executor.run(), 1);
+    }
+
+    #[test]
+    fn test_long_running_task_yields() {
+        // Counts down from 10000 without ever reading input
+        let mut counter = COUNTDOWN.to_vec();
+        counter[1] = 10000;
+        let mut counter = IntComputer::new(counter);
+        let mut echo = IntComputer::new(ECHO.to_vec());
+        let (to_echo, mut echo_rx) = channel();
+        let (mut echo_tx, mut from_echo) = channel();
+        let log = RefCell::new(vec![]);
+
+        let mut executor = LocalExecutor::new();
+        executor.spawn(async {
+            let (_tx, mut rx) = channel();
+            let state = counter.run_async(&mut rx, &mut vec![]).await;
+            assert_eq!(state, Ok(ProgramState::Finished));
+            log.borrow_mut().push("counter");
+        });
+        executor.spawn(async {
+            to_echo.send(1);
+            assert_eq!(from_echo.recv().await, Some(1));
+            log.borrow_mut().push("echo");
+        });
+        executor.spawn(async move {
+            echo.run_async(&mut echo_rx, &mut echo_tx).await.unwrap();
+        });
+        // The echo program keeps waiting for input
+        assert_eq!(executor.run(), 1);
+        drop(executor);
+        assert_eq!(log.into_inner(), vec!["echo", "counter"]);
+    }
+
+    #[test]
+    fn test_output_backpressure() {
+        //  0: ADD #5, #0 -> [100]
+        //  4: OUT [100]
+        //  6: ADD [100], #-1 -> [100]
+        // 10: JT [100], #4
+        // 13: HLT
+        let program = vec![
+            1101, 5, 0, 100, 4, 100, 1001, 100, -1, 100, 1005, 100, 4, 99,
+        ];
+        let mut comp = IntComputer::new(program);
+        let executed = Arc::new(AtomicUsize::new(0));
+        let outputs = Arc::clone(&executed);
+        comp.add_observer(move |access: &MemoryAccess| {
+            if access.pc == 4 {
+                outputs.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        let sink = Bounded::default();
+        let mut received = vec![];
+
+        let mut executor = LocalExecutor::new();
+        let mut output = sink.clone();
+        executor.spawn(async move {
+            let (_tx, mut rx) = channel();
+            let state = comp.run_async(&mut rx, &mut output).await;
+            assert_eq!(state, Ok(ProgramState::Finished));
+        });
+        executor.spawn(async {
+            loop {
+                // Give the computer a chance to run ahead of the consumer
+                yield_now().await;
+                match sink.take() {
+                    Some(value) => {
+                        // At most the value taken and the one waiting for the slot
+                        assert!(executed.load(Ordering::SeqCst) <= received.len() + 2);
+                        received.push(value);
+                    }
+                    None if received.len() == 5 => break,
+                    None => {}
+                }
+            }
+        });
+        assert_eq!(executor.run(), 0);
+        drop(executor);
+        assert_eq!(received, vec![5, 4, 3, 2, 1]);
+    }
+}
@@ -0,0 +1,13 @@
+// Programs used by the tests of several modules
+
+// Echo inputs until a 0 is read, the last value read is kept in cell 9
+pub(crate) const ECHO: [i64; 10] = [3, 9, 4, 9, 1005, 9, 0, 99, 0, 0];
+
+// Count down from 3 in cell 100
+pub(crate) const COUNTDOWN: [i64; 12] = [1101, 3, 0, 100, 1001, 100, -1, 100, 1005, 100, 4, 99];
+
+// Amplifier for the feedback loop of day 7, phases 9, 8, 7, 6, 5 produce 139629729
+pub(crate) const FEEDBACK: [i64; 29] = [
+    3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1, 28, 1005,
+    28, 6, 99, 0, 0, 5,
+];
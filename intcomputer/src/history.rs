@@ -0,0 +1,248 @@
+use crate::{IntComputer, Memory, ProgramState};
+
+// Changes made by one instruction, enough to revert it
+#[derive(Debug, Clone, Default)]
+pub(crate) struct UndoEntry {
+    pc: usize,
+    base: i64,
+    // Memory size before the instruction, writes beyond it grew memory
+    size: usize,
+    // Overwritten cells with their previous values, in order of the writes
+    writes: Vec<(usize, i64)>,
+    // Value consumed from the input queue
+    input: Option<i64>,
+    // Whether a value was appended to the output queue
+    output: bool,
+}
+
+impl<M: Memory> IntComputer<M> {
+    // Record the last limit executed instructions so they can be undone, None stops recording
+    // and discards the history
+    pub fn set_history_limit(&mut self, limit: Option<usize>) {
+        self.history_limit = limit;
+        self.trim_history();
+    }
+
+    pub fn history_limit(&self) -> Option<usize> {
+        self.history_limit
+    }
+
+    // Number of instructions that can be undone
+    pub fn history_len(&self) -> usize {
+        self.history.len()
+    }
+
+    // Undo the last executed instruction, restoring memory, pc, relative base, consumed input
+    // and queued output, returns false if there is no history left
+    // Output passed to a sink or already taken from the queue can't be taken back, stats and
+    // budget aren't rewound
+    pub fn step_back(&mut self) -> bool {
+        let entry = match self.history.pop_back() {
+            Some(entry) => entry,
+            None => return false,
+        };
+        for &(addr, old) in entry.writes.iter().rev() {
+            self.program.store(addr, old);
+        }
+        if self.program.size() > entry.size {
+            self.program.truncate(entry.size);
+        }
+        if let Some(value) = entry.input {
+            self.input.push_front(value);
+        }
+        if entry.output {
+            self.output.pop_back();
+        }
+        self.pc = entry.pc;
+        self.base = entry.base;
+        self.state = ProgramState::Running;
+        true
+    }
+
+    // Undo instructions until the one at addr is about to be executed again
+    // Returns false if the history ran out first, leaving the computer at its oldest state
+    pub fn run_back_to(&mut self, addr: usize) -> bool {
+        while self.step_back() {
+            if self.pc == addr {
+                return true;
+            }
+        }
+        false
+    }
+
+    // Start recording the changes of the instruction at pc
+    pub(crate) fn begin_undo(&mut self) {
+        if self.history_limit.is_some() {
+            self.undo = Some(UndoEntry {
+                pc: self.pc,
+                base: self.base,
+                size: self.program.size(),
+                ..UndoEntry::default()
+            });
+        }
+    }
+
+    pub(crate) fn record_write(&mut self, addr: usize, old: i64) {
+        if let Some(undo) = self.undo.as_mut() {
+            undo.writes.push((addr, old));
+        }
+    }
+
+    pub(crate) fn record_input(&mut self, value: i64) {
+        if let Some(undo) = self.undo.as_mut() {
+            undo.input = Some(value);
+        }
+    }
+
+    pub(crate) fn record_output(&mut self) {
+        if let Some(undo) = self.undo.as_mut() {
+            undo.output = true;
+        }
+    }
+
+    // Add the changes of the completed instruction to the history
+    pub(crate) fn commit_undo(&mut self) {
+        if let Some(undo) = self.undo.take() {
+            if self.history_limit.is_some() {
+                self.history.push_back(undo);
+                self.trim_history();
+            }
+        }
+    }
+
+    // Add the changes an instruction made before it faulted to the history, so they can be
+    // undone as well
+    pub(crate) fn commit_faulted_undo(&mut self) {
+        match &self.undo {
+            Some(undo) if !undo.writes.is_empty() || undo.input.is_some() || undo.output => {
+                self.commit_undo()
+            }
+            _ => self.undo = None,
+        }
+    }
+
+    // Drop the oldest entries beyond the limit
+    fn trim_history(&mut self) {
+        let limit = self.history_limit.unwrap_or(0);
+        if self.history.len() > limit {
+            self.history.drain(..self.history.len() - limit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::{COUNTDOWN, ECHO};
+    use crate::{IntComputer, IntcodeError, Memory, PagedMemory, ProgramState};
+
+    #[test]
+    fn test_step_back() {
+        let mut comp = IntComputer::new(ECHO.to_vec());
+        comp.set_history_limit(Some(100));
+        comp.input.extend(&[5, 0]);
+        assert_eq!(comp.run(), Ok(ProgramState::Finished));
+        assert_eq!(comp.output, vec![5, 0]);
+        assert_eq!(comp.history_len(), 7);
+
+        // Back before the second output
+        assert!(comp.step_back());
+        assert!(comp.step_back());
+        assert!(comp.step_back());
+        assert_eq!(comp.pc(), 2);
+        assert_eq!(comp.output, vec![5]);
+        assert_eq!(comp.state, ProgramState::Running);
+
+        // Back before the second input, which is queued again
+        assert!(comp.step_back());
+        assert_eq!((comp.pc(), comp.peek(9)), (0, 5));
+        assert_eq!(comp.input, vec![0]);
+
+        // Replay with a different value
+        comp.input[0] = 7;
+        comp.input.push_back(0);
+        comp.run().unwrap();
+        assert_eq!(comp.output, vec![5, 7, 0]);
+
+        assert!(comp.run_back_to(0));
+        assert!(comp.run_back_to(0));
+        assert_eq!(comp.input, vec![7, 0]);
+        assert!(comp.run_back_to(0));
+        assert!(!comp.run_back_to(0));
+        assert_eq!(comp.program, ECHO);
+        assert_eq!(comp.input, vec![5, 7, 0]);
+        assert!(comp.output.is_empty());
+    }
+
+    #[test]
+    fn test_history_limit() {
+        let mut comp = IntComputer::new(COUNTDOWN.to_vec());
+        comp.set_history_limit(Some(3));
+        comp.run().unwrap();
+        assert_eq!(comp.history_len(), 3);
+        assert!(comp.run_back_to(4));
+        assert_eq!(comp.peek(100), 1);
+        assert!(!comp.run_back_to(0));
+        assert_eq!((comp.pc(), comp.peek(100)), (4, 1));
+
+        comp.set_history_limit(None);
+        assert_eq!(comp.history_len(), 0);
+        comp.run().unwrap();
+        assert!(!comp.step_back());
+    }
+
+    #[test]
+    fn test_step_back_shrinks_memory() {
+        // Writes to 10, beyond the end of the program
+        let program = vec![1101, 1, 2, 10, 4, 10, 99];
+        let mut comp = IntComputer::new(program.clone());
+        comp.set_history_limit(Some(10));
+        assert_eq!(comp.step(), Ok(ProgramState::Running));
+        assert_eq!(comp.program.size(), 11);
+        assert!(comp.step_back());
+        assert_eq!(comp.program, program);
+
+        let mut comp = IntComputer::with_memory(PagedMemory::new(&program));
+        comp.set_history_limit(Some(10));
+        comp.step().unwrap();
+        assert!(comp.step_back());
+        assert_eq!(comp.program.size(), 7);
+        assert_eq!(comp.program.allocated(), 1024);
+
+        // Beyond the first page
+        let program = vec![1101, 1, 2, 5000, 99];
+        let mut comp = IntComputer::with_memory(PagedMemory::new(&program));
+        comp.set_history_limit(Some(10));
+        comp.step().unwrap();
+        assert_eq!(comp.program.allocated(), 2048);
+        assert!(comp.step_back());
+        assert_eq!(comp.program.size(), 5);
+        assert_eq!(comp.program.allocated(), 1024);
+        assert_eq!(comp.peek(5000), 0);
+    }
+
+    #[test]
+    fn test_step_back_after_fault() {
+        // 50 writes its parameter and then faults
+        let mut comp = IntComputer::new(vec![1101, 1, 2, 7, 50, 8, 99, 0]);
+        comp.set_history_limit(Some(10));
+        comp.register_opcode(50, 1, |ops| {
+            ops.write(0, 7)?;
+            Err(IntcodeError::Overflow { pc: 4 })
+        });
+        assert_eq!(comp.run(), Err(IntcodeError::Overflow { pc: 4 }));
+        assert_eq!(comp.program.len(), 9);
+        assert_eq!(comp.history_len(), 2);
+
+        assert!(comp.step_back());
+        assert_eq!(comp.pc(), 4);
+        assert_eq!(comp.program, vec![1101, 1, 2, 7, 50, 8, 99, 3]);
+        assert!(comp.step_back());
+        assert_eq!(comp.program, vec![1101, 1, 2, 7, 50, 8, 99, 0]);
+
+        // Faults without changes aren't recorded
+        let mut comp = IntComputer::new(vec![1101, 1, 2, 5, 77, 0]);
+        comp.set_history_limit(Some(10));
+        assert!(comp.run().is_err());
+        assert_eq!(comp.history_len(), 1);
+    }
+}
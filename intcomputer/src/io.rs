@@ -0,0 +1,155 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{Receiver, Sender};
+
+// Source of values for input instructions, None makes the program wait for input
+pub trait IntInput {
+    fn read(&mut self) -> Option<i64>;
+}
+
+// Destination of values produced by output instructions
+pub trait IntOutput {
+    fn write(&mut self, value: i64);
+}
+
+impl IntInput for VecDeque<i64> {
+    fn read(&mut self) -> Option<i64> {
+        self.pop_front()
+    }
+}
+
+impl IntOutput for VecDeque<i64> {
+    fn write(&mut self, value: i64) {
+        self.push_back(value);
+    }
+}
+
+impl IntOutput for Vec<i64> {
+    fn write(&mut self, value: i64) {
+        self.push(value);
+    }
+}
+
+impl<F: FnMut() -> Option<i64>> IntInput for F {
+    fn read(&mut self) -> Option<i64> {
+        self()
+    }
+}
+
+impl<F: FnMut(i64)> IntOutput for F {
+    fn write(&mut self, value: i64) {
+        self(value)
+    }
+}
+
+// Never blocks, an empty or disconnected channel makes the program wait for input
+impl IntInput for Receiver<i64> {
+    fn read(&mut self) -> Option<i64> {
+        self.try_recv().ok()
+    }
+}
+
+// Waits for the next value, the program only waits for input once the channel is disconnected
+pub struct BlockingInput {
+    receiver: Receiver<i64>,
+}
+
+impl BlockingInput {
+    pub fn new(receiver: Receiver<i64>) -> Self {
+        Self { receiver }
+    }
+}
+
+impl IntInput for BlockingInput {
+    fn read(&mut self) -> Option<i64> {
+        self.receiver.recv().ok()
+    }
+}
+
+// Values sent after the receiver hung up are dropped
+impl IntOutput for Sender<i64> {
+    fn write(&mut self, value: i64) {
+        let _ = self.send(value);
+    }
+}
+
+// Feeds the characters of a text as ASCII codes
+pub struct AsciiInput {
+    bytes: VecDeque<u8>,
+}
+
+impl AsciiInput {
+    pub fn new(text: &str) -> Self {
+        Self {
+            bytes: text.bytes().collect(),
+        }
+    }
+}
+
+impl IntInput for AsciiInput {
+    fn read(&mut self) -> Option<i64> {
+        self.bytes.pop_front().map(i64::from)
+    }
+}
+
+// Collects ASCII output as text, other values are appended in decimal
+impl IntOutput for String {
+    fn write(&mut self, value: i64) {
+        match value {
+            0..=127 => self.push(value as u8 as char),
+            _ => self.push_str(&value.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::ECHO;
+    use crate::{IntComputer, ProgramState};
+    use std::sync::mpsc;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_closures() {
+        let mut values = vec![3, 2, 1, 0].into_iter();
+        let output = Arc::new(Mutex::new(vec![]));
+        let sink = Arc::clone(&output);
+
+        let mut comp = IntComputer::new(ECHO.to_vec());
+        comp.set_input_source(move || values.next());
+        comp.set_output_sink(move |value| sink.lock().unwrap().push(value * 10));
+        assert_eq!(comp.run(), Ok(ProgramState::Finished));
+        assert_eq!(*output.lock().unwrap(), vec![30, 20, 10, 0]);
+        assert!(comp.output.is_empty());
+    }
+
+    #[test]
+    fn test_channels() {
+        let (in_tx, in_rx) = mpsc::channel();
+        let (out_tx, out_rx) = mpsc::channel();
+        let mut comp = IntComputer::new(ECHO.to_vec());
+        comp.set_input_source(in_rx);
+        comp.set_output_sink(out_tx);
+
+        // Queued input is read before the source
+        comp.input.push_back(1);
+        in_tx.send(2).unwrap();
+        assert_eq!(comp.run(), Ok(ProgramState::WaitingForInput));
+        in_tx.send(0).unwrap();
+        assert_eq!(comp.run(), Ok(ProgramState::Finished));
+        assert_eq!(out_rx.try_iter().collect::<Vec<i64>>(), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn test_ascii() {
+        let text = Arc::new(Mutex::new(String::new()));
+        let sink = Arc::clone(&text);
+        let mut comp = IntComputer::new(ECHO.to_vec());
+        comp.set_input_source(AsciiInput::new("Hi\n"));
+        comp.set_output_sink(move |value| sink.lock().unwrap().write(value));
+        assert_eq!(comp.run(), Ok(ProgramState::WaitingForInput));
+        comp.input.extend(&[1234, 0]);
+        comp.run().unwrap();
+        assert_eq!(*text.lock().unwrap(), "Hi\n1234\0");
+    }
+}
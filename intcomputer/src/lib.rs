@@ -1,35 +1,115 @@
-use std::collections::VecDeque;
-
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::mem;
+
+mod ascii;
+pub mod asm;
+#[cfg(feature = "bigint")]
+mod bigint;
+pub mod cfg;
+mod compiled;
+mod dialect;
+pub mod disasm;
+mod error;
+mod exec;
+pub mod executor;
+#[cfg(test)]
+mod fixtures;
+mod history;
+mod io;
+mod loader;
+mod memory;
+mod network;
+mod observer;
+mod snapshot;
+mod stats;
+pub mod symbolic;
+mod threaded;
+pub mod trace;
+
+#[cfg(feature = "bigint")]
+pub use bigint::BigComputer;
+pub use compiled::CompiledEngine;
+use dialect::CustomOpcode;
+pub use dialect::{Dialect, Operands};
+pub use error::IntcodeError;
+use history::UndoEntry;
+pub use io::{AsciiInput, BlockingInput, IntInput, IntOutput};
+pub use loader::{decode_program, encode_binary, load_program, parse_program, ParseError};
+pub use memory::{Memory, PagedMemory};
+pub use network::{Network, NetworkError, Packets, Pipeline, Ring, Topology};
+pub use observer::{AccessKind, MemoryAccess, Observer};
+pub use snapshot::Snapshot;
+pub use stats::Stats;
+pub use threaded::ComputerThread;
+pub use trace::TraceEntry;
+
+// Instructions beyond this address are decoded on every execution
+const DECODE_CACHE_SIZE: usize = 1 << 16;
+
+// Default bound on allocated memory cells, far above what puzzle programs use
+pub const DEFAULT_MEMORY_LIMIT: usize = 1 << 24;
+
+const LEN_HALT_INSTR: usize = 1;
 const LEN_IO_INSTR: usize = 2;
 const LEN_BASE_INSTR: usize = 2;
 const LEN_JUMP_INSTR: usize = 3;
 const LEN_ARITH_INSTR: usize = 4;
 
-pub struct IntComputer {
-    pub program: Vec<i64>,
+pub struct IntComputer<M = Vec<i64>> {
+    pub program: M,
     pub input: VecDeque<i64>,
     pub output: VecDeque<i64>,
+    // Consulted once the input queue is empty, replaces the output queue if set
+    input_source: Option<Box<dyn IntInput + Send>>,
+    output_sink: Option<Box<dyn IntOutput + Send>>,
 
     base: i64,
     pc: usize,
+    memory_limit: Option<usize>,
+    // Fail with Overflow instead of wrapping around
+    checked: bool,
+    breakpoints: HashSet<usize>,
+    watchpoints: HashSet<usize>,
+    // Watched address changed by the current instruction
+    watch_hit: Option<usize>,
+    observers: Vec<Box<dyn Observer + Send>>,
+    // Operand values and written value of the current instruction, collected for observers
+    trace_operands: Vec<i64>,
+    trace_result: Option<i64>,
+    // Decoded instructions by address along with the opcode word they were decoded from,
+    // an entry is only used while memory still holds that word
+    decoded: Vec<Option<(i64, OpCode)>>,
+    stats: Stats,
+    budget: Option<u64>,
+    // Undo log of the last executed instructions and the entry of the current one
+    history: VecDeque<UndoEntry>,
+    history_limit: Option<usize>,
+    undo: Option<UndoEntry>,
+    dialect: Dialect,
+    // Custom instructions by operation number
+    custom: HashMap<i64, CustomOpcode<M>>,
     pub state: ProgramState,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum ProgramState {
     Finished,
     Running,
     WaitingForInput,
+    Breakpoint(usize),
+    Watchpoint(usize),
+    BudgetExhausted,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 struct OpCode {
     instr: Instruction,
     param_mode: (ParameterMode, ParameterMode, ParameterMode),
 }
 
-#[derive(Debug, PartialEq)]
-enum Instruction {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Instruction {
     Add,
     Multiply,
     Input,
@@ -42,7 +122,20 @@ enum Instruction {
     AdjBase,
 }
 
-#[derive(Debug, PartialEq)]
+const INSTRUCTIONS: [Instruction; 10] = [
+    Instruction::Add,
+    Instruction::Multiply,
+    Instruction::Input,
+    Instruction::Output,
+    Instruction::Halt,
+    Instruction::JumpT,
+    Instruction::JumpF,
+    Instruction::Less,
+    Instruction::Equal,
+    Instruction::AdjBase,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum ParameterMode {
     Position,
     Immediate,
@@ -52,161 +145,480 @@ enum ParameterMode {
 
 impl IntComputer {
     pub fn new(program: Vec<i64>) -> IntComputer {
+        IntComputer::with_memory(program)
+    }
+}
+
+impl<M: Memory> IntComputer<M> {
+    // Create a computer on a different memory backend, initialized with the program
+    pub fn with_memory(program: M) -> IntComputer<M> {
         IntComputer {
-            program: program,
+            program,
             pc: 0,
             base: 0,
+            memory_limit: Some(DEFAULT_MEMORY_LIMIT),
+            checked: false,
+            breakpoints: HashSet::new(),
+            watchpoints: HashSet::new(),
+            watch_hit: None,
+            observers: vec![],
+            trace_operands: vec![],
+            trace_result: None,
+            decoded: vec![],
+            stats: Stats::default(),
+            budget: None,
+            history: VecDeque::new(),
+            history_limit: None,
+            undo: None,
+            dialect: Dialect::default(),
+            custom: HashMap::new(),
             input: VecDeque::new(),
             output: VecDeque::new(),
+            input_source: None,
+            output_sink: None,
             state: ProgramState::Running,
         }
     }
 
-    // Run program until it halts
-    pub fn run(&mut self) {
-        self.state = ProgramState::Running;
+    // Run program until it halts, waits for input, reaches a breakpoint or changes a watched cell
+    // Resuming from a breakpoint or after waiting for input executes the instruction at pc,
+    // breakpoints at any other pc, including the entry point or a pc set by hand, stop run
+    // before executing it
+    // A faulting instruction leaves pc pointing at it and returns the error
+    pub fn run(&mut self) -> Result<ProgramState, IntcodeError> {
+        if self.resuming() {
+            self.step()?;
+        } else {
+            self.state = ProgramState::Running;
+        }
         while self.state == ProgramState::Running {
-            self.exec_instr();
+            if self.at_breakpoint() {
+                self.state = ProgramState::Breakpoint(self.pc);
+            } else {
+                self.exec_instr()?;
+            }
         }
+        Ok(self.state)
     }
 
-    // Execute instruction at current PC
-    fn exec_instr(&mut self) {
-        let opcode = OpCode::new(self.program[self.pc]);
+    // Whether the instruction at pc already passed its breakpoint check
+    fn resuming(&self) -> bool {
+        self.state == ProgramState::Breakpoint(self.pc)
+            || self.state == ProgramState::WaitingForInput
+    }
 
-        match opcode.instr {
-            Instruction::Add | Instruction::Multiply | Instruction::Less | Instruction::Equal => {
-                self.arith(opcode);
+    fn at_breakpoint(&self) -> bool {
+        !self.breakpoints.is_empty() && self.breakpoints.contains(&self.pc)
+    }
+
+    // Execute exactly one instruction and return the resulting state
+    pub fn step(&mut self) -> Result<ProgramState, IntcodeError> {
+        self.state = ProgramState::Running;
+        self.exec_instr()?;
+        Ok(self.state)
+    }
+
+    // Stop run before executing the instruction at addr
+    pub fn add_breakpoint(&mut self, addr: usize) {
+        self.breakpoints.insert(addr);
+    }
+
+    // Returns whether a breakpoint was set at addr
+    pub fn remove_breakpoint(&mut self, addr: usize) -> bool {
+        self.breakpoints.remove(&addr)
+    }
+
+    pub fn breakpoints(&self) -> &HashSet<usize> {
+        &self.breakpoints
+    }
+
+    // Stop run after an instruction changes the value at addr
+    pub fn add_watchpoint(&mut self, addr: usize) {
+        self.watchpoints.insert(addr);
+    }
+
+    // Returns whether a watchpoint was set at addr
+    pub fn remove_watchpoint(&mut self, addr: usize) -> bool {
+        self.watchpoints.remove(&addr)
+    }
+
+    pub fn watchpoints(&self) -> &HashSet<usize> {
+        &self.watchpoints
+    }
+
+    // Report every executed instruction and memory access of the program to observer
+    pub fn add_observer<O: Observer + Send + 'static>(&mut self, observer: O) {
+        self.observers.push(Box::new(observer));
+    }
+
+    pub fn clear_observers(&mut self) {
+        self.observers.clear();
+    }
+
+    // Read input from source whenever the input queue is empty
+    pub fn set_input_source<I: IntInput + Send + 'static>(&mut self, source: I) {
+        self.input_source = Some(Box::new(source));
+    }
+
+    // Send output to sink instead of the output queue
+    pub fn set_output_sink<O: IntOutput + Send + 'static>(&mut self, sink: O) {
+        self.output_sink = Some(Box::new(sink));
+    }
+
+    pub fn clear_input_source(&mut self) {
+        self.input_source = None;
+    }
+
+    pub fn clear_output_sink(&mut self) {
+        self.output_sink = None;
+    }
+
+    // Fail with MemoryLimitExceeded instead of allocating more than limit cells
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.memory_limit = limit;
+    }
+
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit
+    }
+
+    // Fail with Overflow when an addition, multiplication or address computation overflows
+    pub fn set_checked_arithmetic(&mut self, checked: bool) {
+        self.checked = checked;
+    }
+
+    pub fn checked_arithmetic(&self) -> bool {
+        self.checked
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn set_pc(&mut self, pc: usize) {
+        self.pc = pc;
+    }
+
+    // Current relative base
+    pub fn base(&self) -> i64 {
+        self.base
+    }
+
+    pub fn set_base(&mut self, base: i64) {
+        self.base = base;
+    }
+
+    // Read memory without growing it, cells beyond the end read as 0
+    pub fn peek(&self, addr: usize) -> i64 {
+        self.program.load(addr)
+    }
+
+    // Write memory, growing it if necessary, fails if that would exceed the memory limit
+    pub fn poke(&mut self, addr: usize, value: i64) -> Result<(), IntcodeError> {
+        self.store(addr, value).map(|_| ())
+    }
+
+    // Execute instruction at current PC
+    fn exec_instr(&mut self) -> Result<(), IntcodeError> {
+        if self.budget == Some(0) {
+            self.state = ProgramState::BudgetExhausted;
+            return Ok(());
+        }
+        let (pc, base) = (self.pc, self.base);
+        let value = exec::fetch(self)?;
+        self.trace_operands.clear();
+        self.trace_result = None;
+        // A hit left by an instruction that faulted after writing the watched cell
+        self.watch_hit = None;
+        self.begin_undo();
+
+        let instr = match self.dispatch(value) {
+            Ok(instr) => instr,
+            Err(e) => {
+                self.commit_faulted_undo();
+                return Err(e);
             }
-            Instruction::Input | Instruction::Output => {
-                self.io(opcode);
+        };
+
+        // An input instruction without input is retried later and not counted yet
+        if self.state == ProgramState::WaitingForInput {
+            return Ok(());
+        }
+        match instr {
+            Some(instr) => self.stats.record(instr),
+            None => self.stats.record_custom(),
+        }
+        self.commit_undo();
+        if let Some(budget) = self.budget.as_mut() {
+            *budget -= 1;
+        }
+
+        if !self.observers.is_empty() {
+            let entry = TraceEntry {
+                pc,
+                opcode: value,
+                base,
+                operands: mem::take(&mut self.trace_operands),
+                result: self.trace_result,
+            };
+            for observer in self.observers.iter_mut() {
+                observer.on_instruction(&entry);
             }
-            Instruction::JumpF | Instruction::JumpT => {
-                self.jump(opcode);
+        }
+
+        if let Some(addr) = self.watch_hit.take() {
+            self.state = ProgramState::Watchpoint(addr);
+        }
+        Ok(())
+    }
+
+    // Execute the instruction with the given opcode word, None for custom instructions
+    fn dispatch(&mut self, value: i64) -> Result<Option<Instruction>, IntcodeError> {
+        if !self.custom.is_empty() && self.exec_custom(value)? {
+            return Ok(None);
+        }
+        let opcode = self.decode(value)?;
+        exec::execute(self, opcode)?;
+        Ok(Some(opcode.instr))
+    }
+
+    // Decode the opcode word at pc, reusing the cached result while the word is unchanged
+    // Comparing against the word invalidates entries overwritten by self-modifying code
+    // Only instructions valid in the dialect are cached
+    fn decode(&mut self, value: i64) -> Result<OpCode, IntcodeError> {
+        if let Some(Some((word, opcode))) = self.decoded.get(self.pc) {
+            if *word == value {
+                return Ok(*opcode);
             }
-            Instruction::AdjBase => {
-                self.adjust_base(opcode);
+        }
+        let opcode = exec::decode(value, self.pc, &self.dialect)?;
+        if self.pc < DECODE_CACHE_SIZE {
+            if self.pc >= self.decoded.len() {
+                self.decoded.resize(self.pc + 1, None);
             }
-            Instruction::Halt => {
-                self.state = ProgramState::Finished;
+            self.decoded[self.pc] = Some((value, opcode));
+        }
+        Ok(opcode)
+    }
+
+    // Next input value without consuming it
+    // Values from the source are queued so a failing write doesn't lose them
+    fn next_input(&mut self) -> Option<i64> {
+        if self.input.is_empty() {
+            if let Some(value) = self.input_source.as_mut().and_then(|s| s.read()) {
+                self.input.push_back(value);
             }
         }
+        self.input.front().cloned()
     }
 
-    // Handle input/output instructions
-    fn io(&mut self, opcode: OpCode) {
-        let (mode1, _, _) = opcode.param_mode;
-        match opcode.instr {
-            Instruction::Input => {
-                if self.input.is_empty() {
-                    self.state = ProgramState::WaitingForInput;
-                    return;
-                } else {
-                    let input = self.input.pop_front().unwrap();
-                    self.write(1, mode1, input);
-                }
+    fn emit(&mut self, value: i64) {
+        match self.output_sink.as_mut() {
+            Some(sink) => sink.write(value),
+            None => {
+                self.output.push_back(value);
+                self.record_output();
             }
-            Instruction::Output => {
-                let output = self.read(1, mode1);
-                self.output.push_back(output);
+        }
+    }
+
+    // Store value at idx and return the previous value
+    // Overwriting allocated cells is the common case and skips the memory limit, which only
+    // writes allocating storage can exceed
+    fn store(&mut self, idx: usize, value: i64) -> Result<i64, IntcodeError> {
+        if let Some(old) = self.program.replace(idx, value) {
+            return Ok(old);
+        }
+        self.check_limit(idx)?;
+        let old = self.program.load(idx);
+        self.program.store(idx, value);
+        Ok(old)
+    }
+
+    // Make sure writing to idx stays within the memory limit
+    fn check_limit(&self, idx: usize) -> Result<(), IntcodeError> {
+        match self.memory_limit {
+            Some(limit) if self.program.allocated_with(idx) > limit => {
+                Err(IntcodeError::MemoryLimitExceeded {
+                    pc: self.pc,
+                    address: idx,
+                    limit,
+                })
             }
-            _ => {}
+            _ => Ok(()),
         }
-        self.pc += LEN_IO_INSTR;
     }
 
-    fn adjust_base(&mut self, opcode: OpCode) {
-        let (mode1, _, _) = opcode.param_mode;
-        let val = self.read(1, mode1);
-        self.base += val;
-        self.pc += LEN_BASE_INSTR;
+    fn notify(&mut self, addr: usize, kind: AccessKind, old: i64, new: i64) {
+        if self.observers.is_empty() {
+            return;
+        }
+        let access = MemoryAccess {
+            pc: self.pc,
+            addr,
+            kind,
+            old,
+            new,
+        };
+        for observer in self.observers.iter_mut() {
+            observer.on_access(&access);
+        }
     }
+}
 
-    fn jump(&mut self, opcode: OpCode) {
-        let (mode1, mode2, _) = opcode.param_mode;
-        let val = self.read(1, mode1);
-        let dst = self.read(2, mode2) as usize;
+impl<M: Memory> exec::Machine for IntComputer<M> {
+    type Word = i64;
 
-        if (opcode.instr == Instruction::JumpT && val != 0)
-            || (opcode.instr == Instruction::JumpF && val == 0)
-        {
-            if dst < self.program.len() {
-                self.pc = dst
-            }
-        } else {
-            self.pc += LEN_JUMP_INSTR;
+    fn pc(&self) -> usize {
+        self.pc
+    }
+
+    fn set_pc(&mut self, pc: usize) {
+        self.pc = pc;
+    }
+
+    fn base(&self) -> i64 {
+        self.base
+    }
+
+    fn set_base(&mut self, base: i64) {
+        self.base = base;
+    }
+
+    fn set_state(&mut self, state: ProgramState) {
+        self.state = state;
+    }
+
+    fn word(&self, addr: usize) -> Option<i64> {
+        self.program.get(addr)
+    }
+
+    fn read_cell(&mut self, addr: usize) -> i64 {
+        let value = self.program.load(addr);
+        self.stats.access(addr);
+        self.notify(addr, AccessKind::Read, value, value);
+        value
+    }
+
+    fn write_cell(&mut self, addr: usize, value: i64) -> Result<(), IntcodeError> {
+        let old = self.store(addr, value)?;
+        self.record_write(addr, old);
+        self.stats.access(addr);
+        self.trace_result = Some(value);
+
+        if old != value && !self.watchpoints.is_empty() && self.watchpoints.contains(&addr) {
+            self.watch_hit = Some(addr);
         }
+        self.notify(addr, AccessKind::Write, old, value);
+        Ok(())
     }
 
-    // Handle addition, multiplication and comparisons
-    fn arith(&mut self, opcode: OpCode) {
-        let (mode1, mode2, mode3) = opcode.param_mode;
-        let op1 = self.read(1, mode1);
-        let op2 = self.read(2, mode2);
+    fn add(&self, a: i64, b: i64) -> Result<i64, IntcodeError> {
+        if !self.checked {
+            return Ok(a.wrapping_add(b));
+        }
+        a.checked_add(b)
+            .ok_or(IntcodeError::Overflow { pc: self.pc })
+    }
 
-        let result = match opcode.instr {
-            Instruction::Add => op1 + op2,
-            Instruction::Multiply => op1 * op2,
-            Instruction::Less => (op1 < op2) as i64,
-            Instruction::Equal => (op1 == op2) as i64,
-            _ => 0,
-        };
+    fn mul(&self, a: i64, b: i64) -> Result<i64, IntcodeError> {
+        if !self.checked {
+            return Ok(a.wrapping_mul(b));
+        }
+        a.checked_mul(b)
+            .ok_or(IntcodeError::Overflow { pc: self.pc })
+    }
 
-        self.write(3, mode3, result);
-        self.pc += LEN_ARITH_INSTR;
+    fn peek_input(&mut self) -> Option<i64> {
+        self.next_input()
     }
 
-    // Write to memory given by the value at offset
-    // Will interpret the value according to the supplied paramter mode
-    fn write(&mut self, offset: i64, mode: ParameterMode, value: i64) {
-        let val = self.program[self.pc + offset as usize];
-        let idx = match mode {
-            ParameterMode::Position => val as usize,
-            ParameterMode::Relative => (self.base + val) as usize,
-            _ => panic!("Write with unsupported Paramter Mode"),
-        };
-        if idx >= self.program.len() {
-            self.extend_capacity(idx);
+    fn consume_input(&mut self) {
+        if let Some(value) = self.input.pop_front() {
+            self.record_input(value);
         }
-        self.program[idx] = value;
-    }
-
-    // Read from offset relative to current instruction pointer
-    // will consider the supplied parameter mode for direct/indirect read
-    fn read(&mut self, offset: usize, param_mode: ParameterMode) -> i64 {
-        let val = self.program[self.pc + offset];
-        let idx: usize = match param_mode {
-            ParameterMode::Immediate => return val,
-            ParameterMode::Position => val as usize,
-            ParameterMode::Relative => (self.base + val) as usize,
-            ParameterMode::Invalid => panic!("Invalid read mode!"),
-        };
-        if idx >= self.program.len() {
-            self.extend_capacity(idx as usize);
+    }
+
+    fn emit_output(&mut self, value: i64) {
+        self.emit(value);
+    }
+
+    fn operand(&mut self, value: &i64) {
+        if !self.observers.is_empty() {
+            self.trace_operands.push(*value);
+        }
+    }
+}
+
+impl Instruction {
+    // Number of memory cells occupied by the instruction including the opcode
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        match self {
+            Instruction::Add | Instruction::Multiply | Instruction::Less | Instruction::Equal => {
+                LEN_ARITH_INSTR
+            }
+            Instruction::Input | Instruction::Output => LEN_IO_INSTR,
+            Instruction::JumpT | Instruction::JumpF => LEN_JUMP_INSTR,
+            Instruction::AdjBase => LEN_BASE_INSTR,
+            Instruction::Halt => LEN_HALT_INSTR,
         }
-        self.program[idx as usize]
     }
 
-    // Create additional program space for reads/writes beyond the current end of the program
-    fn extend_capacity(&mut self, offset: usize) {
-        let mut extra_capacity: Vec<i64> = vec![0; 1 + offset - self.program.len()];
-        self.program.append(&mut extra_capacity);
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::Add => "ADD",
+            Instruction::Multiply => "MUL",
+            Instruction::Input => "IN",
+            Instruction::Output => "OUT",
+            Instruction::Halt => "HLT",
+            Instruction::JumpT => "JT",
+            Instruction::JumpF => "JF",
+            Instruction::Less => "LT",
+            Instruction::Equal => "EQ",
+            Instruction::AdjBase => "ARB",
+        }
+    }
+
+    pub fn from_mnemonic(mnemonic: &str) -> Option<Instruction> {
+        INSTRUCTIONS
+            .iter()
+            .find(|instr| instr.mnemonic().eq_ignore_ascii_case(mnemonic))
+            .cloned()
+    }
+
+    // Operation number as encoded in the two lowest digits of an opcode
+    pub fn code(&self) -> i64 {
+        match self {
+            Instruction::Add => 1,
+            Instruction::Multiply => 2,
+            Instruction::Input => 3,
+            Instruction::Output => 4,
+            Instruction::JumpT => 5,
+            Instruction::JumpF => 6,
+            Instruction::Less => 7,
+            Instruction::Equal => 8,
+            Instruction::AdjBase => 9,
+            Instruction::Halt => 99,
+        }
+    }
+
+    // Whether the last parameter is a memory location written by the instruction
+    pub fn writes(&self) -> bool {
+        matches!(
+            self,
+            Instruction::Add
+                | Instruction::Multiply
+                | Instruction::Less
+                | Instruction::Equal
+                | Instruction::Input
+        )
     }
 }
 
 impl OpCode {
-    // Parse Instruction and mode flags
-    fn new(opcode: i64) -> OpCode {
-        let read_mode = |flag| match flag % 10 {
-            0 => ParameterMode::Position,
-            1 => ParameterMode::Immediate,
-            2 => ParameterMode::Relative,
-            _ => ParameterMode::Invalid,
-        };
-        let mode1 = read_mode(opcode / 100);
-        let mode2 = read_mode(opcode / 1000);
-        let mode3 = read_mode(opcode / 10000);
-
+    // Parse Instruction and mode flags, None for unknown instructions
+    fn new(opcode: i64) -> Option<OpCode> {
         let operation = opcode % 100;
         let instr = match operation {
             1 => Instruction::Add,
@@ -219,118 +631,228 @@ impl OpCode {
             8 => Instruction::Equal,
             9 => Instruction::AdjBase,
             99 => Instruction::Halt,
-            _ => panic!("Unknown Opcode"),
+            _ => return None,
         };
 
-        OpCode {
-            instr: instr,
-            param_mode: (mode1, mode2, mode3),
-        }
+        Some(OpCode {
+            instr,
+            param_mode: OpCode::modes(opcode),
+        })
+    }
+
+    // Parameter modes encoded in the digits above the operation number
+    fn modes(opcode: i64) -> (ParameterMode, ParameterMode, ParameterMode) {
+        let read_mode = |flag| match flag % 10 {
+            0 => ParameterMode::Position,
+            1 => ParameterMode::Immediate,
+            2 => ParameterMode::Relative,
+            _ => ParameterMode::Invalid,
+        };
+        (
+            read_mode(opcode / 100),
+            read_mode(opcode / 1000),
+            read_mode(opcode / 10000),
+        )
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    #[test]
-    fn test_add() {
-        let mut comp = IntComputer::new(vec![1, 0, 0, 0, 99]);
-        comp.run();
-        assert_eq!(comp.program, vec![2, 0, 0, 0, 99])
-    }
-
-    #[test]
-    fn test_mul() {
-        let mut comp = IntComputer::new(vec![2, 3, 0, 3, 99]);
-        comp.run();
-        assert_eq!(comp.program, vec![2, 3, 0, 6, 99])
-    }
-
     #[test]
     fn test_input() {
         let mut comp = IntComputer::new(vec![3, 0, 99]);
         comp.input.push_back(42);
-        comp.run();
+        comp.run().unwrap();
         assert_eq!(comp.program, vec![42, 0, 99])
     }
 
     #[test]
     fn test_output() {
         let mut comp = IntComputer::new(vec![4, 0, 99]);
-        comp.run();
+        comp.run().unwrap();
         assert_eq!(comp.output.front(), Some(&4))
     }
 
     #[test]
     fn test_relative() {
         let mut comp = IntComputer::new(vec![109, 2019, 109, -19, 99]);
-        comp.run();
+        comp.run().unwrap();
         assert_eq!(comp.base, 2000);
 
         let mut comp = IntComputer::new(vec![204, 2, 99]);
-        comp.run();
+        comp.run().unwrap();
         assert_eq!(comp.output[0], 99);
 
         let mut comp = IntComputer::new(vec![109, 3, 204, -2, 99]);
         comp.input.push_back(42);
-        comp.run();
+        comp.run().unwrap();
         assert_eq!(comp.output[0], 3);
     }
 
     #[test]
-    fn test_p1() {
-        let mut comp = IntComputer::new(vec![2, 4, 4, 5, 99, 0]);
-        comp.run();
-        assert_eq!(comp.program, vec![2, 4, 4, 5, 99, 9801])
+    fn test_errors() {
+        let mut comp = IntComputer::new(vec![42, 0, 0, 0]);
+        assert_eq!(
+            comp.run(),
+            Err(IntcodeError::UnknownOpcode { pc: 0, value: 42 })
+        );
+
+        let mut comp = IntComputer::new(vec![1101, 1, 2, 0, 301, 0, 0, 0, 99]);
+        assert_eq!(
+            comp.run(),
+            Err(IntcodeError::InvalidParameterMode { pc: 4, value: 301 })
+        );
+
+        let mut comp = IntComputer::new(vec![11101, 1, 2, 0, 99]);
+        assert_eq!(comp.run(), Err(IntcodeError::ImmediateWrite { pc: 0 }));
+
+        let mut comp = IntComputer::new(vec![109, -10, 204, 0, 99]);
+        assert_eq!(
+            comp.run(),
+            Err(IntcodeError::NegativeAddress {
+                pc: 2,
+                address: -10
+            })
+        );
+
+        let mut comp = IntComputer::new(vec![1105, 1, 100]);
+        assert_eq!(comp.run(), Err(IntcodeError::PcOutOfBounds { pc: 100 }));
+
+        let mut comp = IntComputer::new(vec![1, 0, 0]);
+        assert_eq!(
+            comp.run(),
+            Err(IntcodeError::TruncatedInstruction { pc: 0 })
+        );
+        assert_eq!(
+            comp.run().unwrap_err().to_string(),
+            "instruction at address 0 is truncated"
+        );
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        // 2^32 * 2^32 doesn't fit
+        let program = vec![1102, 1 << 32, 1 << 32, 7, 4, 7, 99, 0];
+        let mut comp = IntComputer::new(program.clone());
+        comp.set_checked_arithmetic(true);
+        assert_eq!(comp.run(), Err(IntcodeError::Overflow { pc: 0 }));
+        let mut comp = IntComputer::new(program);
+        comp.run().unwrap();
+        assert_eq!(comp.output, vec![0]);
+
+        let mut comp = IntComputer::new(vec![109, i64::MAX, 204, 1, 99]);
+        comp.set_checked_arithmetic(true);
+        assert_eq!(comp.run(), Err(IntcodeError::Overflow { pc: 2 }));
+
+        // Large values that fit are unaffected
+        let mut comp = IntComputer::new(vec![1102, 34915192, 34915192, 7, 4, 7, 99, 0]);
+        comp.set_checked_arithmetic(true);
+        comp.run().unwrap();
+        assert_eq!(comp.output, vec![1219070632396864]);
+    }
+
+    #[test]
+    fn test_failed_input_is_not_consumed() {
+        let mut comp = IntComputer::new(vec![103, 0, 99]);
+        comp.input.push_back(42);
+        assert_eq!(comp.run(), Err(IntcodeError::ImmediateWrite { pc: 0 }));
+        assert_eq!(comp.input.front(), Some(&42));
     }
 
     #[test]
-    fn test_p2() {
-        let mut comp = IntComputer::new(vec![1, 1, 1, 4, 99, 5, 6, 0, 99]);
-        comp.run();
-        assert_eq!(comp.program, vec![30, 1, 1, 4, 2, 5, 6, 0, 99])
+    fn test_step() {
+        let mut comp = IntComputer::new(vec![1101, 1, 2, 7, 3, 0, 99, 0]);
+        assert_eq!(comp.step(), Ok(ProgramState::Running));
+        assert_eq!(comp.pc, 4);
+        assert_eq!(comp.program[7], 3);
+
+        assert_eq!(comp.step(), Ok(ProgramState::WaitingForInput));
+        assert_eq!(comp.pc, 4);
+
+        comp.input.push_back(7);
+        assert_eq!(comp.step(), Ok(ProgramState::Running));
+        assert_eq!(comp.step(), Ok(ProgramState::Finished));
+        assert_eq!(comp.program[0], 7);
     }
 
     #[test]
-    fn test_p3() {
-        let mut comp = IntComputer::new(vec![1, 1, 1, 4, 99, 5, 6, 0, 99]);
-        comp.run();
-        assert_eq!(comp.program, vec![30, 1, 1, 4, 2, 5, 6, 0, 99])
+    fn test_breakpoint() {
+        let mut comp = IntComputer::new(vec![104, 1, 104, 2, 104, 3, 99]);
+        comp.add_breakpoint(4);
+        assert_eq!(comp.run(), Ok(ProgramState::Breakpoint(4)));
+        assert_eq!(comp.output, vec![1, 2]);
+
+        // Resuming executes the instruction at the breakpoint
+        assert_eq!(comp.run(), Ok(ProgramState::Finished));
+        assert_eq!(comp.output, vec![1, 2, 3]);
+
+        assert!(comp.remove_breakpoint(4));
+        assert!(!comp.remove_breakpoint(4));
     }
 
     #[test]
-    fn test_p4() {
-        let mut comp = IntComputer::new(vec![
-            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
-            0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
-            20, 1105, 1, 46, 98, 99,
-        ]);
-        comp.input.push_back(9);
-        comp.run();
-        assert_eq!(comp.output.front(), Some(&1001))
+    fn test_breakpoint_before_first_instruction() {
+        let mut comp = IntComputer::new(vec![104, 1, 104, 2, 99]);
+        comp.add_breakpoint(0);
+        assert_eq!(comp.run(), Ok(ProgramState::Breakpoint(0)));
+        assert!(comp.output.is_empty());
+        assert_eq!(comp.run(), Ok(ProgramState::Finished));
+        assert_eq!(comp.output, vec![1, 2]);
+
+        // A pc set by hand
+        let mut comp = IntComputer::new(vec![104, 1, 104, 2, 99]);
+        comp.add_breakpoint(2);
+        comp.set_pc(2);
+        assert_eq!(comp.run(), Ok(ProgramState::Breakpoint(2)));
+        assert!(comp.output.is_empty());
+        assert_eq!(comp.run(), Ok(ProgramState::Finished));
+        assert_eq!(comp.output, vec![2]);
+
+        // An input instruction at a breakpoint stops once, not again when input arrives
+        let mut comp = IntComputer::new(vec![3, 0, 99]);
+        comp.add_breakpoint(0);
+        assert_eq!(comp.run(), Ok(ProgramState::Breakpoint(0)));
+        assert_eq!(comp.run(), Ok(ProgramState::WaitingForInput));
+        comp.input.push_back(5);
+        assert_eq!(comp.run(), Ok(ProgramState::Finished));
     }
 
     #[test]
-    fn test_p5() {
-        let mut comp = IntComputer::new(vec![
-            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
-            0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
-            20, 1105, 1, 46, 98, 99,
-        ]);
-        comp.input.push_back(8);
-        comp.run();
-        assert_eq!(comp.output.front(), Some(&1000))
+    fn test_inspection() {
+        let mut comp = IntComputer::new(vec![109, 5, 204, 1, 99]);
+        comp.step().unwrap();
+        assert_eq!((comp.pc(), comp.base()), (2, 5));
+
+        // Redirect the output to a cell beyond the end of the program
+        assert_eq!(comp.peek(6), 0);
+        assert_eq!(comp.program.len(), 5);
+        comp.poke(6, 1234).unwrap();
+        assert_eq!(comp.program.len(), 7);
+        assert_eq!(
+            comp.poke(1_000_000_000_000, 1),
+            Err(IntcodeError::MemoryLimitExceeded {
+                pc: 2,
+                address: 1_000_000_000_000,
+                limit: DEFAULT_MEMORY_LIMIT
+            })
+        );
+        assert_eq!(comp.program.len(), 7);
+        comp.run().unwrap();
+        assert_eq!(comp.output, vec![1234]);
+
+        comp.set_pc(2);
+        comp.set_base(-1);
+        comp.run().unwrap();
+        assert_eq!(comp.output, vec![1234, 109]);
     }
 
     #[test]
-    fn test_p6() {
-        let mut comp = IntComputer::new(vec![
-            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
-            0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
-            20, 1105, 1, 46, 98, 99,
-        ]);
-        comp.input.push_back(7);
-        comp.run();
-        assert_eq!(comp.output.front(), Some(&999))
+    fn test_self_modifying() {
+        // Output 7, then overwrite the output instruction with a halt and jump back to it
+        let mut comp = IntComputer::new(vec![104, 7, 1101, 0, 99, 0, 1105, 1, 0]);
+        comp.set_budget(Some(100));
+        assert_eq!(comp.run(), Ok(ProgramState::Finished));
+        assert_eq!(comp.output, vec![7]);
     }
 }
@@ -0,0 +1,281 @@
+use crate::IntComputer;
+use flate2::read::GzDecoder;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+// Header of the binary format, followed by the values as zigzag encoded LEB128 varints
+const MAGIC: &[u8; 4] = b"INTB";
+const VERSION: u8 = 1;
+
+const GZIP_MAGIC: &[u8; 2] = b"\x1f\x8b";
+// Bound on the size of decompressed programs, far above the text of any puzzle program
+const MAX_DECOMPRESSED_SIZE: u64 = 1 << 26;
+
+// Error in a textual program, lines and columns are counted from 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl Error for ParseError {}
+
+// Parse a program given as text
+//
+// Values are separated by commas, whitespace or newlines and comments run from `#` or `;` to
+// the end of the line. A trailing comma is accepted, empty values between commas are not.
+pub fn parse_program(text: &str) -> Result<Vec<i64>, ParseError> {
+    let mut program = vec![];
+    // Set at the start and after every comma, a second comma is missing a value
+    let mut expect_value = true;
+
+    for (idx, line) in text.lines().enumerate() {
+        let code = match line.find(['#', ';']) {
+            Some(pos) => &line[..pos],
+            None => line,
+        };
+        let err = |pos: usize, message: String| ParseError {
+            line: idx + 1,
+            column: code[..pos].chars().count() + 1,
+            message,
+        };
+
+        let mut pos = 0;
+        while let Some(c) = code[pos..].chars().next() {
+            if c.is_whitespace() {
+                pos += c.len_utf8();
+                continue;
+            }
+            if c == ',' {
+                if expect_value {
+                    return Err(err(pos, "missing value before ','".to_string()));
+                }
+                expect_value = true;
+                pos += 1;
+                continue;
+            }
+            let end = code[pos..]
+                .find(|c: char| c == ',' || c.is_whitespace())
+                .map_or(code.len(), |len| pos + len);
+            let token = &code[pos..end];
+            let value = token.parse::<i64>().map_err(|_| {
+                let digits = token.trim_start_matches(['-', '+']);
+                if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+                    err(pos, format!("value '{}' is out of range", token))
+                } else {
+                    err(pos, format!("invalid value '{}'", token))
+                }
+            })?;
+            program.push(value);
+            expect_value = false;
+            pos = end;
+        }
+    }
+    Ok(program)
+}
+
+// Encode a program in the compact binary format
+pub fn encode_binary(program: &[i64]) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.push(VERSION);
+    for &value in program {
+        let mut zigzag = ((value << 1) ^ (value >> 63)) as u64;
+        loop {
+            let byte = (zigzag & 0x7f) as u8;
+            zigzag >>= 7;
+            if zigzag == 0 {
+                bytes.push(byte);
+                break;
+            }
+            bytes.push(byte | 0x80);
+        }
+    }
+    bytes
+}
+
+fn decode_binary(bytes: &[u8]) -> io::Result<Vec<i64>> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    if bytes.get(MAGIC.len()) != Some(&VERSION) {
+        return Err(invalid("unsupported binary program version".to_string()));
+    }
+    let mut program = vec![];
+    let mut zigzag = 0u64;
+    let mut shift = 0;
+    for (offset, &byte) in bytes.iter().enumerate().skip(MAGIC.len() + 1) {
+        if shift > 63 {
+            return Err(invalid(format!("value at byte {} is too long", offset)));
+        }
+        // Only the lowest bit of the 10th byte still fits into 64 bits
+        if shift == 63 && byte & 0x7e != 0 {
+            return Err(invalid(format!("value at byte {} is out of range", offset)));
+        }
+        zigzag |= u64::from(byte & 0x7f) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            program.push((zigzag >> 1) as i64 ^ -((zigzag & 1) as i64));
+            zigzag = 0;
+            shift = 0;
+        }
+    }
+    if shift != 0 {
+        return Err(invalid("truncated value at the end".to_string()));
+    }
+    Ok(program)
+}
+
+// Decode a program in any of the supported formats: text, the binary format or either of
+// them compressed with gzip
+// Parse errors are returned as InvalidData errors wrapping a ParseError
+pub fn decode_program(bytes: &[u8]) -> io::Result<Vec<i64>> {
+    if bytes.starts_with(GZIP_MAGIC) {
+        let decompressed = inflate(bytes, MAX_DECOMPRESSED_SIZE)?;
+        if decompressed.starts_with(GZIP_MAGIC) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "nested gzip compression",
+            ));
+        }
+        return decode_program(&decompressed);
+    }
+    if bytes.starts_with(MAGIC) {
+        return decode_binary(bytes);
+    }
+    let text = std::str::from_utf8(bytes).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("program is neither text nor binary: {}", e),
+        )
+    })?;
+    parse_program(text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+// Decompress gzip data, failing once it exceeds limit bytes
+fn inflate(bytes: &[u8], limit: u64) -> io::Result<Vec<u8>> {
+    let mut decompressed = vec![];
+    GzDecoder::new(bytes)
+        .take(limit + 1)
+        .read_to_end(&mut decompressed)?;
+    if decompressed.len() as u64 > limit {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("program is larger than {} bytes when decompressed", limit),
+        ));
+    }
+    Ok(decompressed)
+}
+
+// Read a program from a file in any of the formats accepted by decode_program
+pub fn load_program<P: AsRef<Path>>(path: P) -> io::Result<Vec<i64>> {
+    decode_program(&fs::read(path)?)
+}
+
+impl IntComputer {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<IntComputer> {
+        Ok(IntComputer::new(load_program(path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    #[test]
+    fn test_text() {
+        let text = "; day 2 example\n1,9,10,3,\n2, 3, 11, 0 # multiply\n99\n30 40 50,\n";
+        assert_eq!(
+            parse_program(text),
+            Ok(vec![1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50])
+        );
+        assert_eq!(parse_program("104,-3,99\n"), Ok(vec![104, -3, 99]));
+        assert_eq!(parse_program(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |line, column, message: &str| {
+            Err(ParseError {
+                line,
+                column,
+                message: message.to_string(),
+            })
+        };
+        assert_eq!(
+            parse_program("1,0,0,0,\n99,x1,3"),
+            error(2, 4, "invalid value 'x1'")
+        );
+        assert_eq!(
+            parse_program("1,,0"),
+            error(1, 3, "missing value before ','")
+        );
+        assert_eq!(parse_program(",1"), error(1, 1, "missing value before ','"));
+        assert_eq!(
+            parse_program("  99999999999999999999"),
+            error(1, 3, "value '99999999999999999999' is out of range")
+        );
+    }
+
+    #[test]
+    fn test_formats() {
+        let program = vec![1, 0, -1, 63, -64, 64, i64::MAX, i64::MIN, 99];
+        let binary = encode_binary(&program);
+        assert_eq!(binary.len(), 5 + 5 + 2 + 2 * 10 + 2);
+        assert_eq!(decode_program(&binary).unwrap(), program);
+
+        let mut gzip = GzEncoder::new(vec![], Compression::default());
+        gzip.write_all(b"1,0,0,0,99\n").unwrap();
+        assert_eq!(
+            decode_program(&gzip.finish().unwrap()).unwrap(),
+            vec![1, 0, 0, 0, 99]
+        );
+
+        let mut gzip = GzEncoder::new(vec![], Compression::default());
+        gzip.write_all(&binary).unwrap();
+        assert_eq!(decode_program(&gzip.finish().unwrap()).unwrap(), program);
+
+        let error = decode_program(&binary[..binary.len() - 1]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let error = decode_program(b"1,2,three").unwrap_err();
+        let parse_error = error.get_ref().unwrap().downcast_ref::<ParseError>();
+        assert_eq!(parse_error.map(|e| e.column), Some(5));
+    }
+
+    #[test]
+    fn test_malformed_input() {
+        // The 10th byte of a varint may only carry the highest bit of the value
+        let mut binary = encode_binary(&[i64::MIN]);
+        assert_eq!(binary[binary.len() - 1], 0x01);
+        assert_eq!(decode_program(&binary).unwrap(), vec![i64::MIN]);
+        *binary.last_mut().unwrap() = 0x03;
+        let error = decode_program(&binary).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "value at byte 14 is out of range");
+
+        let mut gzip = GzEncoder::new(vec![], Compression::default());
+        gzip.write_all(&[b'0'; 1000]).unwrap();
+        let gzip = gzip.finish().unwrap();
+        assert_eq!(inflate(&gzip, 1000).unwrap().len(), 1000);
+        let error = inflate(&gzip, 999).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_from_file() {
+        let comp = IntComputer::from_file("../aoc09/input").unwrap();
+        assert_eq!(&comp.program[..3], &[1102, 34463338, 34463338]);
+        assert!(IntComputer::from_file("../aoc09/missing").is_err());
+    }
+}
@@ -0,0 +1,239 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::mem;
+
+const PAGE_SIZE: usize = 1024;
+// Pages for the first megabyte of cells are kept in a vector instead of the map
+const DIRECT_PAGES: usize = 1024;
+
+// Storage backend for the memory of an IntComputer
+// Cells that were never written read as 0, writes allocate storage as needed
+pub trait Memory {
+    // Number of cells from address 0 up to the highest written address
+    fn size(&self) -> usize;
+
+    fn load(&self, addr: usize) -> i64;
+
+    fn store(&mut self, addr: usize, value: i64);
+
+    // Value at addr if it lies below size
+    fn get(&self, addr: usize) -> Option<i64> {
+        if addr < self.size() {
+            Some(self.load(addr))
+        } else {
+            None
+        }
+    }
+
+    // Overwrite a cell that is already backed by storage and return its previous value
+    // None if writing addr has to allocate, memory is left unchanged then
+    fn replace(&mut self, _addr: usize, _value: i64) -> Option<i64> {
+        None
+    }
+
+    // Shrink to size cells, cells beyond read as 0 again and are no longer backed by storage
+    fn truncate(&mut self, size: usize);
+
+    // Number of cells backed by storage
+    fn allocated(&self) -> usize;
+
+    // Number of cells backed by storage once addr has been written
+    fn allocated_with(&self, addr: usize) -> usize;
+}
+
+// Dense backend, growing the vector up to the highest written address
+impl Memory for Vec<i64> {
+    fn size(&self) -> usize {
+        self.len()
+    }
+
+    fn load(&self, addr: usize) -> i64 {
+        <[i64]>::get(self, addr).cloned().unwrap_or(0)
+    }
+
+    fn store(&mut self, addr: usize, value: i64) {
+        if addr >= self.len() {
+            self.resize(addr + 1, 0);
+        }
+        self[addr] = value;
+    }
+
+    fn get(&self, addr: usize) -> Option<i64> {
+        <[i64]>::get(self, addr).cloned()
+    }
+
+    fn replace(&mut self, addr: usize, value: i64) -> Option<i64> {
+        <[i64]>::get_mut(self, addr).map(|cell| mem::replace(cell, value))
+    }
+
+    fn truncate(&mut self, size: usize) {
+        Vec::truncate(self, size);
+    }
+
+    fn allocated(&self) -> usize {
+        self.len()
+    }
+
+    fn allocated_with(&self, addr: usize) -> usize {
+        self.len().max(addr + 1)
+    }
+}
+
+// Sparse backend allocating fixed size pages on first write
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PagedMemory {
+    direct: Vec<Option<Vec<i64>>>,
+    pages: HashMap<usize, Vec<i64>>,
+    // Number of allocated pages
+    allocated: usize,
+    size: usize,
+}
+
+impl PagedMemory {
+    pub fn new(program: &[i64]) -> Self {
+        let mut memory = PagedMemory::default();
+        for (addr, &value) in program.iter().enumerate() {
+            memory.store(addr, value);
+        }
+        memory
+    }
+}
+
+impl PagedMemory {
+    fn page(&self, page: usize) -> Option<&Vec<i64>> {
+        if page < DIRECT_PAGES {
+            self.direct.get(page)?.as_ref()
+        } else {
+            self.pages.get(&page)
+        }
+    }
+
+    fn page_mut(&mut self, page: usize) -> Option<&mut Vec<i64>> {
+        if page < DIRECT_PAGES {
+            self.direct.get_mut(page)?.as_mut()
+        } else {
+            self.pages.get_mut(&page)
+        }
+    }
+}
+
+impl Memory for PagedMemory {
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn load(&self, addr: usize) -> i64 {
+        match self.page(addr / PAGE_SIZE) {
+            Some(page) => page[addr % PAGE_SIZE],
+            None => 0,
+        }
+    }
+
+    fn store(&mut self, addr: usize, value: i64) {
+        let page = addr / PAGE_SIZE;
+        if self.page(page).is_none() {
+            self.allocated += 1;
+        }
+        let page = if page < DIRECT_PAGES {
+            if page >= self.direct.len() {
+                self.direct.resize(page + 1, None);
+            }
+            self.direct[page].get_or_insert_with(|| vec![0; PAGE_SIZE])
+        } else {
+            self.pages.entry(page).or_insert_with(|| vec![0; PAGE_SIZE])
+        };
+        page[addr % PAGE_SIZE] = value;
+        self.size = self.size.max(addr + 1);
+    }
+
+    fn replace(&mut self, addr: usize, value: i64) -> Option<i64> {
+        let page = self.page_mut(addr / PAGE_SIZE)?;
+        let old = mem::replace(&mut page[addr % PAGE_SIZE], value);
+        self.size = self.size.max(addr + 1);
+        Some(old)
+    }
+
+    fn truncate(&mut self, size: usize) {
+        if size >= self.size {
+            return;
+        }
+        // The page holding size keeps the cells below it, later pages are dropped
+        if !size.is_multiple_of(PAGE_SIZE) {
+            if let Some(page) = self.page_mut(size / PAGE_SIZE) {
+                page[size % PAGE_SIZE..]
+                    .iter_mut()
+                    .for_each(|cell| *cell = 0);
+            }
+        }
+        let first = size.div_ceil(PAGE_SIZE);
+        for page in self.direct.iter_mut().skip(first) {
+            if page.take().is_some() {
+                self.allocated -= 1;
+            }
+        }
+        let pages = self.pages.len();
+        self.pages.retain(|&page, _| page < first);
+        self.allocated -= pages - self.pages.len();
+        self.size = size;
+    }
+
+    fn allocated(&self) -> usize {
+        self.allocated * PAGE_SIZE
+    }
+
+    fn allocated_with(&self, addr: usize) -> usize {
+        if self.page(addr / PAGE_SIZE).is_some() {
+            self.allocated()
+        } else {
+            self.allocated() + PAGE_SIZE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IntComputer, IntcodeError, ProgramState};
+
+    #[test]
+    fn test_paged() {
+        let program = vec![1101, 20, 22, 1_000_000_000_000, 4, 1_000_000_000_000, 99];
+        let mut comp = IntComputer::with_memory(PagedMemory::new(&program));
+        assert_eq!(comp.run(), Ok(ProgramState::Finished));
+        assert_eq!(comp.output, vec![42]);
+        assert_eq!(comp.program.size(), 1_000_000_000_001);
+        assert_eq!(comp.program.allocated(), 2 * PAGE_SIZE);
+
+        comp.program.truncate(PAGE_SIZE + 1);
+        assert_eq!(comp.program.size(), PAGE_SIZE + 1);
+        assert_eq!(comp.program.allocated(), PAGE_SIZE);
+        assert_eq!(comp.peek(1_000_000_000_000), 0);
+        assert_eq!(comp.peek(1), 20);
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        let program = vec![1101, 1, 2, 1_000_000_000_000, 99];
+        let mut comp = IntComputer::new(program.clone());
+        comp.set_memory_limit(Some(1 << 20));
+        assert_eq!(
+            comp.run(),
+            Err(IntcodeError::MemoryLimitExceeded {
+                pc: 0,
+                address: 1_000_000_000_000,
+                limit: 1 << 20,
+            })
+        );
+
+        let mut comp = IntComputer::with_memory(PagedMemory::new(&program));
+        comp.set_memory_limit(Some(1 << 20));
+        assert_eq!(comp.run(), Ok(ProgramState::Finished));
+        assert_eq!(comp.peek(1_000_000_000_000), 3);
+
+        // Cells that are already allocated can be overwritten beyond the limit
+        let mut comp = IntComputer::new(vec![1101, 1, 2, 5, 99, 0]);
+        comp.set_memory_limit(Some(4));
+        assert_eq!(comp.run(), Ok(ProgramState::Finished));
+        assert_eq!(comp.peek(5), 3);
+    }
+}
@@ -0,0 +1,250 @@
+use crate::{IntComputer, IntcodeError, ProgramState};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+
+// Decides where the output of the machines in a network is delivered
+pub trait Topology {
+    // Remove complete messages from the output of machine src and return them with their
+    // destination, None sends a message out of the network
+    fn route(
+        &mut self,
+        src: usize,
+        machines: usize,
+        output: &mut VecDeque<i64>,
+    ) -> Vec<(Option<usize>, Vec<i64>)>;
+}
+
+// Every machine feeds the next one, the last one feeds the network output
+pub struct Pipeline;
+
+// Every machine feeds the next one, the last one feeds the first
+pub struct Ring;
+
+// Output consists of packets of len values starting with the address of the receiving machine,
+// the address is stripped on delivery
+// Packets to addresses outside the network are sent out of it unchanged
+pub struct Packets {
+    len: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetworkError {
+    Fault { machine: usize, error: IntcodeError },
+    BudgetExhausted { machine: usize },
+    // All machines that didn't finish wait for input nobody is going to send
+    Deadlock { waiting: Vec<usize> },
+}
+
+// Set of computers connected by a topology, scheduled round-robin
+pub struct Network<T> {
+    machines: Vec<IntComputer>,
+    topology: T,
+    // Messages sent out of the network or to machines that already finished
+    pub output: VecDeque<i64>,
+}
+
+impl Topology for Pipeline {
+    fn route(
+        &mut self,
+        src: usize,
+        machines: usize,
+        output: &mut VecDeque<i64>,
+    ) -> Vec<(Option<usize>, Vec<i64>)> {
+        let dst = Some(src + 1).filter(|&dst| dst < machines);
+        vec![(dst, output.drain(..).collect())]
+    }
+}
+
+impl Topology for Ring {
+    fn route(
+        &mut self,
+        src: usize,
+        machines: usize,
+        output: &mut VecDeque<i64>,
+    ) -> Vec<(Option<usize>, Vec<i64>)> {
+        vec![(Some((src + 1) % machines), output.drain(..).collect())]
+    }
+}
+
+impl Packets {
+    // Packets hold at least the address, so len has to be positive
+    pub fn new(len: usize) -> Packets {
+        assert!(len > 0, "packets need room for the address");
+        Packets { len }
+    }
+}
+
+impl Topology for Packets {
+    fn route(
+        &mut self,
+        _src: usize,
+        machines: usize,
+        output: &mut VecDeque<i64>,
+    ) -> Vec<(Option<usize>, Vec<i64>)> {
+        let mut messages = vec![];
+        while output.len() >= self.len {
+            let packet: Vec<i64> = output.drain(..self.len).collect();
+            match packet[0] {
+                addr if addr >= 0 && (addr as usize) < machines => {
+                    messages.push((Some(addr as usize), packet[1..].to_vec()))
+                }
+                _ => messages.push((None, packet)),
+            }
+        }
+        messages
+    }
+}
+
+impl<T: Topology> Network<T> {
+    pub fn new(machines: Vec<IntComputer>, topology: T) -> Self {
+        Self {
+            machines,
+            topology,
+            output: VecDeque::new(),
+        }
+    }
+
+    pub fn machines(&self) -> &[IntComputer] {
+        &self.machines
+    }
+
+    pub fn machine_mut(&mut self, idx: usize) -> &mut IntComputer {
+        &mut self.machines[idx]
+    }
+
+    // Run the machines in turn until all of them finished
+    // Each machine runs until it stops on its own, then its output is routed
+    pub fn run(&mut self) -> Result<(), NetworkError> {
+        loop {
+            let mut progress = false;
+            for idx in 0..self.machines.len() {
+                let machine = &mut self.machines[idx];
+                let blocked =
+                    machine.state == ProgramState::WaitingForInput && machine.input.is_empty();
+                if machine.state == ProgramState::Finished || blocked {
+                    continue;
+                }
+                progress = true;
+                machine.run().map_err(|error| NetworkError::Fault {
+                    machine: idx,
+                    error,
+                })?;
+                self.deliver(idx);
+                if self.machines[idx].state == ProgramState::BudgetExhausted {
+                    return Err(NetworkError::BudgetExhausted { machine: idx });
+                }
+            }
+
+            if !progress {
+                let waiting: Vec<usize> = (0..self.machines.len())
+                    .filter(|&idx| self.machines[idx].state != ProgramState::Finished)
+                    .collect();
+                if waiting.is_empty() {
+                    return Ok(());
+                }
+                return Err(NetworkError::Deadlock { waiting });
+            }
+        }
+    }
+
+    fn deliver(&mut self, src: usize) {
+        let count = self.machines.len();
+        let messages = self
+            .topology
+            .route(src, count, &mut self.machines[src].output);
+        for (dst, values) in messages {
+            match dst {
+                Some(dst) if self.machines[dst].state != ProgramState::Finished => {
+                    self.machines[dst].input.extend(values)
+                }
+                _ => self.output.extend(values),
+            }
+        }
+    }
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetworkError::Fault { machine, error } => write!(f, "machine {}: {}", machine, error),
+            NetworkError::BudgetExhausted { machine } => {
+                write!(f, "machine {} exhausted its budget", machine)
+            }
+            NetworkError::Deadlock { waiting } => {
+                write!(f, "deadlock, machines {:?} wait for input", waiting)
+            }
+        }
+    }
+}
+
+impl Error for NetworkError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::FEEDBACK;
+
+    // Amplifier from day 7, reads a phase and a signal and outputs 10 * signal + phase
+    const AMPLIFIER: [i64; 17] = [
+        3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+    ];
+
+    fn machines(program: &[i64], phases: &[i64]) -> Vec<IntComputer> {
+        phases
+            .iter()
+            .map(|&phase| {
+                let mut comp = IntComputer::new(program.to_vec());
+                comp.input.push_back(phase);
+                comp
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_pipeline() {
+        let mut network = Network::new(machines(&AMPLIFIER, &[4, 3, 2, 1, 0]), Pipeline);
+        network.machine_mut(0).input.push_back(0);
+        network.run().unwrap();
+        assert_eq!(network.output, vec![43210]);
+    }
+
+    #[test]
+    fn test_ring() {
+        let mut network = Network::new(machines(&FEEDBACK, &[9, 8, 7, 6, 5]), Ring);
+        network.machine_mut(0).input.push_back(0);
+        network.run().unwrap();
+        assert_eq!(network.output, vec![139629729]);
+    }
+
+    #[test]
+    fn test_packets() {
+        // Machine 0 sends (1, 7) to machine 1 and (5, 8) out of the network,
+        // machine 1 echoes what it receives to address 9
+        let sender = vec![104, 1, 104, 7, 104, 5, 104, 8, 99];
+        let receiver = vec![109, 9, 203, 0, 104, 9, 204, 0, 99];
+        let mut network = Network::new(
+            vec![IntComputer::new(sender), IntComputer::new(receiver)],
+            Packets::new(2),
+        );
+        network.run().unwrap();
+        assert_eq!(network.output, vec![5, 8, 9, 7]);
+    }
+
+    #[test]
+    fn test_deadlock() {
+        let mut network = Network::new(machines(&AMPLIFIER, &[0, 1]), Ring);
+        assert_eq!(
+            network.run(),
+            Err(NetworkError::Deadlock {
+                waiting: vec![0, 1]
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "packets need room for the address")]
+    fn test_empty_packets() {
+        Packets::new(0);
+    }
+}
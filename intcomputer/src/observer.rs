@@ -0,0 +1,98 @@
+use crate::TraceEntry;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+// Data access performed by the instruction at pc
+// Reads report the same old and new value, cells beyond the end of the program read as 0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryAccess {
+    pub pc: usize,
+    pub addr: usize,
+    pub kind: AccessKind,
+    pub old: i64,
+    pub new: i64,
+}
+
+// Hook notified about VM activity, registered with IntComputer::add_observer
+// Operand reads in immediate mode and instruction fetches are not memory accesses
+pub trait Observer {
+    fn on_access(&mut self, _access: &MemoryAccess) {}
+
+    // Called after an instruction completed
+    fn on_instruction(&mut self, _entry: &TraceEntry) {}
+}
+
+impl<F: FnMut(&MemoryAccess)> Observer for F {
+    fn on_access(&mut self, access: &MemoryAccess) {
+        self(access)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IntComputer, IntcodeError, ProgramState};
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_access_trace() {
+        let (tx, rx) = mpsc::channel();
+        let mut comp = IntComputer::new(vec![1, 5, 6, 7, 99, 2, 3]);
+        comp.add_observer(move |access: &MemoryAccess| tx.send(*access).unwrap());
+        comp.run().unwrap();
+
+        let access = |addr, kind, old, new| MemoryAccess {
+            pc: 0,
+            addr,
+            kind,
+            old,
+            new,
+        };
+        let trace: Vec<MemoryAccess> = rx.try_iter().collect();
+        assert_eq!(
+            trace,
+            vec![
+                access(5, AccessKind::Read, 2, 2),
+                access(6, AccessKind::Read, 3, 3),
+                // Writes beyond the end of the program see the zero filled cell
+                access(7, AccessKind::Write, 0, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_watchpoint() {
+        // Increment cell 100 three times, the second add writes the same value back
+        let mut comp = IntComputer::new(vec![
+            1001, 100, 1, 100, 1001, 100, 0, 100, 1001, 100, 1, 100, 99,
+        ]);
+        comp.add_watchpoint(100);
+        assert_eq!(comp.run(), Ok(ProgramState::Watchpoint(100)));
+        assert_eq!((comp.pc(), comp.peek(100)), (4, 1));
+        assert_eq!(comp.run(), Ok(ProgramState::Watchpoint(100)));
+        assert_eq!((comp.pc(), comp.peek(100)), (12, 2));
+
+        assert!(comp.remove_watchpoint(100));
+        assert_eq!(comp.run(), Ok(ProgramState::Finished));
+    }
+
+    #[test]
+    fn test_watchpoint_after_fault() {
+        // 50 writes the watched cell 7 and then faults, the OUT #1 at 2 doesn't touch it
+        let mut comp = IntComputer::new(vec![50, 7, 104, 1, 99, 0, 0, 0]);
+        comp.add_watchpoint(7);
+        comp.register_opcode(50, 1, |ops| {
+            ops.write(0, 1)?;
+            Err(IntcodeError::Overflow { pc: 0 })
+        });
+        assert_eq!(comp.run(), Err(IntcodeError::Overflow { pc: 0 }));
+
+        comp.set_pc(2);
+        assert_eq!(comp.run(), Ok(ProgramState::Finished));
+        assert_eq!(comp.output, vec![1]);
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{IntComputer, Memory, ProgramState};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+// Complete execution state of an IntComputer
+// Breakpoints, watchpoints and observers are debugging aids and not part of the snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot<M = Vec<i64>> {
+    pub memory: M,
+    pub pc: usize,
+    pub base: i64,
+    pub input: VecDeque<i64>,
+    pub output: VecDeque<i64>,
+    pub state: ProgramState,
+}
+
+impl<M: Memory + Clone> IntComputer<M> {
+    pub fn snapshot(&self) -> Snapshot<M> {
+        Snapshot {
+            memory: self.program.clone(),
+            pc: self.pc,
+            base: self.base,
+            input: self.input.clone(),
+            output: self.output.clone(),
+            state: self.state,
+        }
+    }
+
+    // Reset the execution state to the snapshot, keeping breakpoints, watchpoints and observers
+    // The undo history doesn't apply to the restored state and is discarded
+    pub fn restore(&mut self, snapshot: &Snapshot<M>) {
+        self.program.clone_from(&snapshot.memory);
+        self.pc = snapshot.pc;
+        self.base = snapshot.base;
+        self.input.clone_from(&snapshot.input);
+        self.output.clone_from(&snapshot.output);
+        self.state = snapshot.state;
+        self.history.clear();
+    }
+
+    pub fn from_snapshot(snapshot: &Snapshot<M>) -> IntComputer<M> {
+        let mut comp = IntComputer::with_memory(snapshot.memory.clone());
+        comp.restore(snapshot);
+        comp
+    }
+}
+
+// Clones copy breakpoints, watchpoints, limits, stats and the dialect and share the handlers of
+// custom instructions with the original
+// Observers, the input source and the output sink can't be cloned, the clone starts without
+// them and with an empty undo history
+impl<M: Memory + Clone> Clone for IntComputer<M> {
+    fn clone(&self) -> Self {
+        let mut comp = IntComputer::from_snapshot(&self.snapshot());
+        comp.memory_limit = self.memory_limit;
+        comp.checked = self.checked;
+        comp.stats = self.stats.clone();
+        comp.budget = self.budget;
+        comp.history_limit = self.history_limit;
+        comp.dialect = self.dialect;
+        comp.custom = self.custom.clone();
+        comp.breakpoints = self.breakpoints.clone();
+        comp.watchpoints = self.watchpoints.clone();
+        comp
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::ECHO;
+
+    #[test]
+    fn test_restore() {
+        let mut comp = IntComputer::new(ECHO.to_vec());
+        comp.input.push_back(1);
+        assert_eq!(comp.run(), Ok(ProgramState::WaitingForInput));
+        let snapshot = comp.snapshot();
+
+        comp.input.push_back(2);
+        comp.run().unwrap();
+        assert_eq!(comp.output, vec![1, 2]);
+
+        // Branch off with different input from the same point
+        comp.restore(&snapshot);
+        comp.input.extend(&[3, 0]);
+        assert_eq!(comp.run(), Ok(ProgramState::Finished));
+        assert_eq!(comp.output, vec![1, 3, 0]);
+    }
+
+    #[test]
+    fn test_clone() {
+        let mut comp = IntComputer::new(ECHO.to_vec());
+        comp.input.push_back(5);
+        comp.run().unwrap();
+
+        let mut other = comp.clone();
+        other.input.push_back(0);
+        assert_eq!(other.run(), Ok(ProgramState::Finished));
+        assert_eq!(comp.state, ProgramState::WaitingForInput);
+        assert_eq!(comp.output, vec![5]);
+        assert_eq!(other.output, vec![5, 0]);
+    }
+
+    #[test]
+    fn test_clone_custom_opcodes() {
+        // 50: output the parameter twice
+        let mut comp = IntComputer::new(vec![150, 7, 99]);
+        comp.register_opcode(50, 1, |ops| {
+            let value = ops.read(0)?;
+            ops.computer().output.extend(&[value, value]);
+            Ok(())
+        });
+        let mut other = comp.clone();
+        assert_eq!(other.run(), Ok(ProgramState::Finished));
+        assert_eq!(other.output, vec![7, 7]);
+    }
+
+    #[test]
+    fn test_serialize() {
+        let mut comp = IntComputer::new(ECHO.to_vec());
+        comp.input.extend(&[7, 8]);
+        comp.step().unwrap();
+
+        let json = serde_json::to_string(&comp.snapshot()).unwrap();
+        let snapshot: Snapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(snapshot, comp.snapshot());
+
+        let mut restored = IntComputer::from_snapshot(&snapshot);
+        restored.input.push_back(0);
+        restored.run().unwrap();
+        assert_eq!(restored.output, vec![7, 8, 0]);
+    }
+}
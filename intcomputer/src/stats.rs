@@ -0,0 +1,100 @@
+use crate::{Instruction, IntComputer, Memory, INSTRUCTIONS};
+use std::fmt;
+
+// Execution statistics collected since the computer was created or the stats were reset
+// Input instructions count once they received their input
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stats {
+    pub instructions: u64,
+    // Highest memory address read or written by an instruction
+    pub memory_high_water: usize,
+    counts: [u64; INSTRUCTIONS.len()],
+}
+
+impl Stats {
+    // Number of times instr was executed
+    pub fn count(&self, instr: Instruction) -> u64 {
+        self.counts[instr as usize]
+    }
+
+    pub(crate) fn record(&mut self, instr: Instruction) {
+        self.instructions += 1;
+        self.counts[instr as usize] += 1;
+    }
+
+    // Custom instructions only count towards the total
+    pub(crate) fn record_custom(&mut self) {
+        self.instructions += 1;
+    }
+
+    pub(crate) fn access(&mut self, addr: usize) {
+        self.memory_high_water = self.memory_high_water.max(addr);
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "instructions: {}", self.instructions)?;
+        writeln!(f, "memory high-water mark: {}", self.memory_high_water)?;
+        for &instr in INSTRUCTIONS.iter() {
+            writeln!(f, "{:<4} {}", instr.mnemonic(), self.count(instr))?;
+        }
+        Ok(())
+    }
+}
+
+impl<M: Memory> IntComputer<M> {
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = Stats::default();
+    }
+
+    // Stop with BudgetExhausted once budget more instructions have been executed
+    pub fn set_budget(&mut self, budget: Option<u64>) {
+        self.budget = budget;
+    }
+
+    // Number of instructions left until the budget is exhausted
+    pub fn budget(&self) -> Option<u64> {
+        self.budget
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::COUNTDOWN;
+    use crate::{Instruction, IntComputer, ProgramState};
+
+    #[test]
+    fn test_stats() {
+        let mut comp = IntComputer::new(COUNTDOWN.to_vec());
+        comp.run().unwrap();
+        let stats = comp.stats();
+        assert_eq!(stats.instructions, 8);
+        assert_eq!(stats.count(Instruction::Add), 4);
+        assert_eq!(stats.count(Instruction::JumpT), 3);
+        assert_eq!(stats.count(Instruction::Halt), 1);
+        assert_eq!(stats.count(Instruction::Multiply), 0);
+        assert_eq!(stats.memory_high_water, 100);
+
+        comp.reset_stats();
+        assert_eq!(comp.stats().instructions, 0);
+    }
+
+    #[test]
+    fn test_budget() {
+        // Loop forever
+        let mut comp = IntComputer::new(vec![1105, 1, 0]);
+        comp.set_budget(Some(10));
+        assert_eq!(comp.run(), Ok(ProgramState::BudgetExhausted));
+        assert_eq!(comp.stats().instructions, 10);
+        assert_eq!(comp.step(), Ok(ProgramState::BudgetExhausted));
+
+        comp.set_budget(Some(5));
+        assert_eq!(comp.run(), Ok(ProgramState::BudgetExhausted));
+        assert_eq!(comp.stats().instructions, 15);
+    }
+}
@@ -0,0 +1,738 @@
+use crate::{Instruction, IntComputer, IntcodeError, OpCode, ParameterMode, ProgramState};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::slice;
+
+// Instructions after which a run is abandoned unless set otherwise
+const DEFAULT_BUDGET: u64 = 1_000_000;
+// Cells memory may grow to unless set otherwise, symbolic cells are far larger than integers
+const DEFAULT_MEMORY_LIMIT: usize = 1 << 16;
+
+// Value the program is executed over symbolically
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Unknown {
+    // Initial value of a memory cell
+    Cell(usize),
+    // Value read by the nth input instruction, counting from 0
+    Input(usize),
+}
+
+// Polynomial over unknowns with integer coefficients
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Expr {
+    // Nonzero coefficients by monomial, the sorted unknowns multiplied in the term
+    terms: BTreeMap<Vec<Unknown>, i64>,
+}
+
+// Where the value solved for ends up once the program stops
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Cell(usize),
+    // The nth output value, counting from 0
+    Output(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SymbolicError {
+    Fault(IntcodeError),
+    // An opcode, a jump, the relative base or a write address depends on unknowns
+    Unsupported { pc: usize, reason: &'static str },
+    BudgetExhausted,
+}
+
+// Memory and output of a symbolic execution
+// Values that depend on unknowns in a way expressions can't capture are None, e.g. comparisons
+// of unknowns or reads through addresses computed from unknowns
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolicState {
+    pub memory: Vec<Option<Expr>>,
+    pub output: Vec<Option<Expr>>,
+    // False if the program stopped waiting for input
+    pub finished: bool,
+}
+
+// Finds values for unknowns that make a program produce a given value
+pub struct Solver {
+    program: Vec<i64>,
+    unknowns: Vec<(Unknown, RangeInclusive<i64>)>,
+    input: Vec<i64>,
+    budget: u64,
+    memory_limit: usize,
+}
+
+struct Machine<'a> {
+    memory: Vec<Option<Expr>>,
+    output: Vec<Option<Expr>>,
+    pc: usize,
+    base: i64,
+    // Opcode word of the current instruction
+    opcode: i64,
+    unknown_inputs: HashSet<usize>,
+    input: slice::Iter<'a, i64>,
+    reads: usize,
+    memory_limit: usize,
+}
+
+impl Expr {
+    pub fn constant(value: i64) -> Expr {
+        let mut terms = BTreeMap::new();
+        if value != 0 {
+            terms.insert(vec![], value);
+        }
+        Expr { terms }
+    }
+
+    pub fn unknown(unknown: Unknown) -> Expr {
+        let mut terms = BTreeMap::new();
+        terms.insert(vec![unknown], 1);
+        Expr { terms }
+    }
+
+    pub fn as_constant(&self) -> Option<i64> {
+        match self.terms.len() {
+            0 => Some(0),
+            1 => self.terms.get(&vec![]).cloned(),
+            _ => None,
+        }
+    }
+
+    // Highest number of unknowns multiplied in a term, 1 for linear expressions
+    pub fn degree(&self) -> usize {
+        self.terms.keys().map(Vec::len).max().unwrap_or(0)
+    }
+
+    // Coefficient of the term multiplying the unknowns, which have to be sorted
+    pub fn coefficient(&self, monomial: &[Unknown]) -> i64 {
+        self.terms.get(monomial).cloned().unwrap_or(0)
+    }
+
+    // Value for the given unknowns, None on overflow or if an unknown is missing
+    pub fn eval(&self, values: &HashMap<Unknown, i64>) -> Option<i64> {
+        self.terms
+            .iter()
+            .try_fold(0i64, |sum, (monomial, &coefficient)| {
+                let term = monomial.iter().try_fold(coefficient, |product, unknown| {
+                    product.checked_mul(*values.get(unknown)?)
+                })?;
+                sum.checked_add(term)
+            })
+    }
+
+    fn checked_add(&self, other: &Expr) -> Option<Expr> {
+        let mut terms = self.terms.clone();
+        for (monomial, &coefficient) in other.terms.iter() {
+            let sum = terms.get(monomial).unwrap_or(&0).checked_add(coefficient)?;
+            if sum == 0 {
+                terms.remove(monomial);
+            } else {
+                terms.insert(monomial.clone(), sum);
+            }
+        }
+        Some(Expr { terms })
+    }
+
+    fn checked_mul(&self, other: &Expr) -> Option<Expr> {
+        let mut product = Expr::default();
+        for (left, &a) in self.terms.iter() {
+            for (right, &b) in other.terms.iter() {
+                let mut monomial: Vec<Unknown> = left.iter().chain(right).cloned().collect();
+                monomial.sort_unstable();
+                let mut term = Expr::default();
+                term.terms.insert(monomial, a.checked_mul(b)?);
+                product = product.checked_add(&term)?;
+            }
+        }
+        Some(product)
+    }
+}
+
+impl fmt::Display for Unknown {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Unknown::Cell(addr) => write!(f, "[{}]", addr),
+            Unknown::Input(n) => write!(f, "in{}", n),
+        }
+    }
+}
+
+// Terms of higher degree first, e.g. `2*[1]*[2] + 3*in0 - 5`
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.terms.is_empty() {
+            return write!(f, "0");
+        }
+        let mut terms: Vec<_> = self.terms.iter().collect();
+        terms.sort_by_key(|(monomial, _)| std::cmp::Reverse(monomial.len()));
+        for (idx, (monomial, &coefficient)) in terms.into_iter().enumerate() {
+            match (idx, coefficient < 0) {
+                (0, true) => write!(f, "-")?,
+                (0, false) => {}
+                (_, true) => write!(f, " - ")?,
+                (_, false) => write!(f, " + ")?,
+            }
+            let factors: Vec<String> = monomial.iter().map(Unknown::to_string).collect();
+            match coefficient.unsigned_abs() {
+                1 if !factors.is_empty() => write!(f, "{}", factors.join("*"))?,
+                abs if factors.is_empty() => write!(f, "{}", abs)?,
+                abs => write!(f, "{}*{}", abs, factors.join("*"))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SymbolicError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SymbolicError::Fault(error) => write!(f, "{}", error),
+            SymbolicError::Unsupported { pc, reason } => {
+                write!(f, "{} at address {}", reason, pc)
+            }
+            SymbolicError::BudgetExhausted => write!(f, "instruction budget exhausted"),
+        }
+    }
+}
+
+impl Error for SymbolicError {}
+
+impl SymbolicState {
+    // Value at target, None if it can't be expressed or the program produced no such output
+    pub fn value(&self, target: Target) -> Option<Expr> {
+        match target {
+            Target::Cell(addr) => self
+                .memory
+                .get(addr)
+                .cloned()
+                .unwrap_or(Some(Expr::default())),
+            Target::Output(n) => self.output.get(n).cloned().flatten(),
+        }
+    }
+}
+
+impl<'a> Machine<'a> {
+    // Execute one instruction, Some(finished) once the program halts or waits for input
+    fn step(&mut self) -> Result<Option<bool>, SymbolicError> {
+        let pc = self.pc;
+        self.opcode = self.word(pc)?.and_then(|word| word.as_constant()).ok_or(
+            SymbolicError::Unsupported {
+                pc,
+                reason: "opcode depends on unknowns",
+            },
+        )?;
+        let opcode =
+            OpCode::new(self.opcode).ok_or(SymbolicError::Fault(IntcodeError::UnknownOpcode {
+                pc,
+                value: self.opcode,
+            }))?;
+        let (mode1, mode2, mode3) = opcode.param_mode;
+        let overflow = SymbolicError::Fault(IntcodeError::Overflow { pc });
+
+        match opcode.instr {
+            Instruction::Add | Instruction::Multiply | Instruction::Less | Instruction::Equal => {
+                let result = match (self.read(1, mode1)?, self.read(2, mode2)?) {
+                    (Some(a), Some(b)) => match opcode.instr {
+                        Instruction::Add => Some(a.checked_add(&b).ok_or(overflow)?),
+                        Instruction::Multiply => Some(a.checked_mul(&b).ok_or(overflow)?),
+                        // Comparisons are only known if the difference is constant
+                        _ => a
+                            .checked_add(&b.checked_mul(&Expr::constant(-1)).ok_or(overflow)?)
+                            .and_then(|diff| diff.as_constant())
+                            .map(|diff| {
+                                let result = match opcode.instr {
+                                    Instruction::Less => diff < 0,
+                                    _ => diff == 0,
+                                };
+                                Expr::constant(result as i64)
+                            }),
+                    },
+                    _ => None,
+                };
+                self.write(3, mode3, result)?;
+            }
+            Instruction::Input => {
+                let value = if self.unknown_inputs.contains(&self.reads) {
+                    Expr::unknown(Unknown::Input(self.reads))
+                } else {
+                    match self.input.next() {
+                        Some(&value) => Expr::constant(value),
+                        None => return Ok(Some(false)),
+                    }
+                };
+                self.write(1, mode1, Some(value))?;
+                self.reads += 1;
+            }
+            Instruction::Output => {
+                let value = self.read(1, mode1)?;
+                self.output.push(value);
+            }
+            Instruction::JumpT | Instruction::JumpF => {
+                let condition = self.constant(1, mode1, "jump condition depends on unknowns")?;
+                let dst = self.constant(2, mode2, "jump target depends on unknowns")?;
+                if (condition != 0) == (opcode.instr == Instruction::JumpT) {
+                    if dst < 0 {
+                        return Err(SymbolicError::Fault(IntcodeError::NegativeAddress {
+                            pc,
+                            address: dst,
+                        }));
+                    }
+                    self.pc = dst as usize;
+                    return Ok(None);
+                }
+            }
+            Instruction::AdjBase => {
+                let value = self.constant(1, mode1, "relative base depends on unknowns")?;
+                self.base = self.base.checked_add(value).ok_or(overflow)?;
+            }
+            Instruction::Halt => return Ok(Some(true)),
+        }
+        self.pc += opcode.instr.len();
+        Ok(None)
+    }
+
+    fn word(&self, addr: usize) -> Result<Option<Expr>, SymbolicError> {
+        match self.memory.get(addr) {
+            Some(word) => Ok(word.clone()),
+            None => Err(SymbolicError::Fault(IntcodeError::PcOutOfBounds {
+                pc: addr,
+            })),
+        }
+    }
+
+    fn param(&self, offset: usize) -> Result<Option<Expr>, SymbolicError> {
+        self.word(self.pc + offset)
+            .map_err(|_| SymbolicError::Fault(IntcodeError::TruncatedInstruction { pc: self.pc }))
+    }
+
+    fn read(&self, offset: usize, mode: ParameterMode) -> Result<Option<Expr>, SymbolicError> {
+        let word = self.param(offset)?;
+        if mode == ParameterMode::Immediate {
+            return Ok(word);
+        }
+        Ok(match self.address(word, mode)? {
+            Some(addr) => self
+                .memory
+                .get(addr)
+                .cloned()
+                .unwrap_or(Some(Expr::default())),
+            None => None,
+        })
+    }
+
+    fn constant(
+        &self,
+        offset: usize,
+        mode: ParameterMode,
+        reason: &'static str,
+    ) -> Result<i64, SymbolicError> {
+        self.read(offset, mode)?
+            .and_then(|value| value.as_constant())
+            .ok_or(SymbolicError::Unsupported {
+                pc: self.pc,
+                reason,
+            })
+    }
+
+    fn write(
+        &mut self,
+        offset: usize,
+        mode: ParameterMode,
+        value: Option<Expr>,
+    ) -> Result<(), SymbolicError> {
+        let pc = self.pc;
+        if mode == ParameterMode::Immediate {
+            return Err(SymbolicError::Fault(IntcodeError::ImmediateWrite { pc }));
+        }
+        let word = self.param(offset)?;
+        let addr = self
+            .address(word, mode)?
+            .ok_or(SymbolicError::Unsupported {
+                pc,
+                reason: "write address depends on unknowns",
+            })?;
+        let zero = Some(Expr::default());
+        grow(&mut self.memory, zero, addr, self.memory_limit, pc).map_err(SymbolicError::Fault)?;
+        self.memory[addr] = value;
+        Ok(())
+    }
+
+    // Address referenced by a parameter, None if it depends on unknowns
+    fn address(
+        &self,
+        word: Option<Expr>,
+        mode: ParameterMode,
+    ) -> Result<Option<usize>, SymbolicError> {
+        let pc = self.pc;
+        let value = match word.and_then(|word| word.as_constant()) {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        let address = match mode {
+            ParameterMode::Position => value,
+            ParameterMode::Relative => self
+                .base
+                .checked_add(value)
+                .ok_or(SymbolicError::Fault(IntcodeError::Overflow { pc }))?,
+            _ => {
+                return Err(SymbolicError::Fault(IntcodeError::InvalidParameterMode {
+                    pc,
+                    value: self.opcode,
+                }))
+            }
+        };
+        if address < 0 {
+            return Err(SymbolicError::Fault(IntcodeError::NegativeAddress {
+                pc,
+                address,
+            }));
+        }
+        Ok(Some(address as usize))
+    }
+}
+
+// Make memory hold addr by appending zero cells, failing if that would exceed limit cells
+// Cells that are already allocated can always be written, like in IntComputer
+fn grow<T: Clone>(
+    memory: &mut Vec<T>,
+    zero: T,
+    addr: usize,
+    limit: usize,
+    pc: usize,
+) -> Result<(), IntcodeError> {
+    if addr < memory.len() {
+        return Ok(());
+    }
+    if addr >= limit {
+        return Err(IntcodeError::MemoryLimitExceeded {
+            pc,
+            address: addr,
+            limit,
+        });
+    }
+    memory.resize(addr + 1, zero);
+    Ok(())
+}
+
+// Call f for every combination of values from the ranges in lexicographic order until it
+// returns a result
+fn find_combination<T>(
+    ranges: &[RangeInclusive<i64>],
+    mut f: impl FnMut(&[i64]) -> Option<T>,
+) -> Option<T> {
+    if ranges.iter().any(|range| range.is_empty()) {
+        return None;
+    }
+    let mut values: Vec<i64> = ranges.iter().map(|range| *range.start()).collect();
+    loop {
+        if let Some(result) = f(&values) {
+            return Some(result);
+        }
+        let mut idx = ranges.len();
+        loop {
+            if idx == 0 {
+                return None;
+            }
+            idx -= 1;
+            if values[idx] < *ranges[idx].end() {
+                values[idx] += 1;
+                break;
+            }
+            values[idx] = *ranges[idx].start();
+        }
+    }
+}
+
+impl Solver {
+    pub fn new(program: Vec<i64>) -> Solver {
+        Solver {
+            program,
+            unknowns: vec![],
+            input: vec![],
+            budget: DEFAULT_BUDGET,
+            memory_limit: DEFAULT_MEMORY_LIMIT,
+        }
+    }
+
+    // Solve for unknown, considering the values in range
+    pub fn add_unknown(&mut self, unknown: Unknown, range: RangeInclusive<i64>) {
+        self.unknowns.push((unknown, range));
+    }
+
+    // Values for the input instructions not declared unknown, in order
+    pub fn set_input(&mut self, input: Vec<i64>) {
+        self.input = input;
+    }
+
+    // Abandon runs after budget instructions
+    pub fn set_budget(&mut self, budget: u64) {
+        self.budget = budget;
+    }
+
+    // Fail with MemoryLimitExceeded instead of growing memory beyond limit cells, both for
+    // unknown cells and for writes of the program
+    pub fn set_memory_limit(&mut self, limit: usize) {
+        self.memory_limit = limit;
+    }
+
+    // Run the program with the unknowns as symbols until it halts or waits for input
+    // Control flow has to be independent of the unknowns
+    pub fn execute(&self) -> Result<SymbolicState, SymbolicError> {
+        let mut memory: Vec<Option<Expr>> = self
+            .program
+            .iter()
+            .map(|&value| Some(Expr::constant(value)))
+            .collect();
+        let mut unknown_inputs = HashSet::new();
+        for &(unknown, _) in self.unknowns.iter() {
+            match unknown {
+                Unknown::Cell(addr) => {
+                    grow(
+                        &mut memory,
+                        Some(Expr::default()),
+                        addr,
+                        self.memory_limit,
+                        0,
+                    )
+                    .map_err(SymbolicError::Fault)?;
+                    memory[addr] = Some(Expr::unknown(unknown));
+                }
+                Unknown::Input(n) => {
+                    unknown_inputs.insert(n);
+                }
+            }
+        }
+
+        let mut machine = Machine {
+            memory,
+            output: vec![],
+            pc: 0,
+            base: 0,
+            opcode: 0,
+            unknown_inputs,
+            input: self.input.iter(),
+            reads: 0,
+            memory_limit: self.memory_limit,
+        };
+        for _ in 0..self.budget {
+            if let Some(finished) = machine.step()? {
+                return Ok(SymbolicState {
+                    memory: machine.memory,
+                    output: machine.output,
+                    finished,
+                });
+            }
+        }
+        Err(SymbolicError::BudgetExhausted)
+    }
+
+    // Find values for the unknowns, in the order they were added, that make the program stop
+    // with value at target
+    //
+    // Linear results are solved for the last unknown they depend on, trying every combination
+    // of the others. Everything else is brute-forced on the interpreter. Candidates are always
+    // confirmed by a run of the interpreter and the first solution in lexicographic order is
+    // returned.
+    pub fn solve(&self, target: Target, value: i64) -> Option<Vec<i64>> {
+        match self.execute().ok().and_then(|state| state.value(target)) {
+            Some(expr) if expr.degree() <= 1 => self.solve_linear(&expr, target, value),
+            _ => find_combination(&self.ranges(), |values| {
+                Some(values.to_vec()).filter(|values| self.check(values, target, value))
+            }),
+        }
+    }
+
+    fn ranges(&self) -> Vec<RangeInclusive<i64>> {
+        self.unknowns
+            .iter()
+            .map(|(_, range)| range.clone())
+            .collect()
+    }
+
+    fn solve_linear(&self, expr: &Expr, target: Target, value: i64) -> Option<Vec<i64>> {
+        let coefficients: Vec<i128> = self
+            .unknowns
+            .iter()
+            .map(|&(unknown, _)| i128::from(expr.coefficient(&[unknown])))
+            .collect();
+        let solved = coefficients.iter().rposition(|&c| c != 0);
+        // Unknowns without influence keep their first value
+        let ranges: Vec<RangeInclusive<i64>> = self
+            .ranges()
+            .into_iter()
+            .enumerate()
+            .map(|(idx, range)| match coefficients[idx] {
+                _ if Some(idx) == solved => *range.start()..=*range.start(),
+                0 => *range.start()..=*range.start(),
+                _ => range,
+            })
+            .collect();
+        let constant = i128::from(expr.coefficient(&[]));
+
+        find_combination(&ranges, |values| {
+            let mut values = values.to_vec();
+            let sum: i128 = values
+                .iter()
+                .zip(coefficients.iter())
+                .enumerate()
+                .filter(|&(idx, _)| Some(idx) != solved)
+                .map(|(_, (&value, &c))| i128::from(value) * c)
+                .sum();
+            let rest = i128::from(value) - constant - sum;
+            match solved {
+                Some(idx) => {
+                    if rest % coefficients[idx] != 0 {
+                        return None;
+                    }
+                    let solution = i64::try_from(rest / coefficients[idx]).ok()?;
+                    if !self.unknowns[idx].1.contains(&solution) {
+                        return None;
+                    }
+                    values[idx] = solution;
+                }
+                None if rest != 0 => return None,
+                None => {}
+            }
+            Some(values).filter(|values| self.check(values, target, value))
+        })
+    }
+
+    // Run the program on the interpreter with the unknowns set to values
+    fn check(&self, values: &[i64], target: Target, value: i64) -> bool {
+        let mut program = self.program.clone();
+        let mut unknown_inputs = HashMap::new();
+        for (&(unknown, _), &value) in self.unknowns.iter().zip(values) {
+            match unknown {
+                Unknown::Cell(addr) => {
+                    if grow(&mut program, 0, addr, self.memory_limit, 0).is_err() {
+                        return false;
+                    }
+                    program[addr] = value;
+                }
+                Unknown::Input(n) => {
+                    unknown_inputs.insert(n, value);
+                }
+            }
+        }
+
+        let mut comp = IntComputer::new(program);
+        comp.set_budget(Some(self.budget));
+        comp.set_memory_limit(Some(self.memory_limit));
+        comp.set_checked_arithmetic(true);
+        let mut input = self.input.clone().into_iter();
+        let mut reads = 0;
+        comp.set_input_source(move || {
+            let value = match unknown_inputs.get(&reads) {
+                Some(&value) => Some(value),
+                None => input.next(),
+            };
+            if value.is_some() {
+                reads += 1;
+            }
+            value
+        });
+        match comp.run() {
+            Ok(ProgramState::Finished) | Ok(ProgramState::WaitingForInput) => {}
+            _ => return false,
+        }
+        match target {
+            Target::Cell(addr) => comp.peek(addr) == value,
+            Target::Output(n) => comp.output.get(n) == Some(&value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expressions() {
+        //  0: IN -> [20]
+        //  2: IN -> [21]
+        //  4: MUL [20], #-3 -> [22]
+        //  8: ADD [22], [21] -> [22]
+        // 12: ADD [22], #5 -> [22]
+        // 16: OUT [22]
+        // 18: HLT
+        let program = vec![
+            3, 20, 3, 21, 1002, 20, -3, 22, 1, 22, 21, 22, 1001, 22, 5, 22, 4, 22, 99,
+        ];
+        let mut solver = Solver::new(program);
+        solver.add_unknown(Unknown::Input(0), -100..=100);
+        solver.set_input(vec![7]);
+        let state = solver.execute().unwrap();
+        assert!(state.finished);
+        let output = state.value(Target::Output(0)).unwrap();
+        assert_eq!(output.to_string(), "-3*in0 + 12");
+        assert_eq!(output.degree(), 1);
+
+        let values = vec![(Unknown::Input(0), 4)].into_iter().collect();
+        assert_eq!(output.eval(&values), Some(0));
+        assert_eq!(solver.solve(Target::Output(0), 0), Some(vec![4]));
+        assert_eq!(solver.solve(Target::Output(0), 1), None);
+    }
+
+    #[test]
+    fn test_gravity_assist() {
+        let program = crate::parse_program(include_str!("../../aoc02/input")).unwrap();
+        let mut solver = Solver::new(program);
+        solver.add_unknown(Unknown::Cell(1), 0..=99);
+        solver.add_unknown(Unknown::Cell(2), 0..=99);
+        let result = solver.execute().unwrap().value(Target::Cell(0)).unwrap();
+        assert_eq!(result.degree(), 1);
+        assert_eq!(solver.solve(Target::Cell(0), 19690720), Some(vec![69, 79]));
+    }
+
+    #[test]
+    fn test_fallback() {
+        // Product of the inputs
+        let program = vec![3, 9, 3, 10, 2, 9, 10, 11, 99];
+        let mut solver = Solver::new(program);
+        solver.add_unknown(Unknown::Input(0), 0..=10);
+        solver.add_unknown(Unknown::Input(1), 0..=10);
+        let result = solver.execute().unwrap().value(Target::Cell(11)).unwrap();
+        assert_eq!(result.to_string(), "in0*in1");
+        assert_eq!(solver.solve(Target::Cell(11), 42), Some(vec![6, 7]));
+
+        // Output 1 if the input is 0, otherwise 2
+        let program = vec![3, 11, 1005, 11, 8, 104, 1, 99, 104, 2, 99];
+        let mut solver = Solver::new(program);
+        solver.add_unknown(Unknown::Input(0), -5..=5);
+        assert_eq!(
+            solver.execute(),
+            Err(SymbolicError::Unsupported {
+                pc: 2,
+                reason: "jump condition depends on unknowns"
+            })
+        );
+        assert_eq!(solver.solve(Target::Output(0), 1), Some(vec![0]));
+        assert_eq!(solver.solve(Target::Output(0), 2), Some(vec![-5]));
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        let limit_exceeded = |pc, address| {
+            Err(SymbolicError::Fault(IntcodeError::MemoryLimitExceeded {
+                pc,
+                address,
+                limit: 100,
+            }))
+        };
+        let mut solver = Solver::new(vec![1101, 1, 2, 99, 99]);
+        solver.set_memory_limit(100);
+        assert!(solver.execute().is_ok());
+        solver.add_unknown(Unknown::Cell(usize::MAX), 0..=1);
+        assert_eq!(solver.execute(), limit_exceeded(0, usize::MAX));
+        assert_eq!(solver.solve(Target::Cell(3), 3), None);
+
+        // A symbolic write
+        let mut solver = Solver::new(vec![3, 100, 99]);
+        solver.set_memory_limit(100);
+        solver.add_unknown(Unknown::Input(0), 0..=1);
+        assert_eq!(solver.execute(), limit_exceeded(0, 100));
+        solver.set_memory_limit(101);
+        assert_eq!(solver.solve(Target::Cell(100), 1), Some(vec![1]));
+    }
+}
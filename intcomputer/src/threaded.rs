@@ -0,0 +1,105 @@
+use crate::{BlockingInput, IntComputer, IntcodeError, Memory, ProgramState};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+// Computer running on its own thread, connected through channels
+pub struct ComputerThread<M = Vec<i64>> {
+    pub input: Sender<i64>,
+    pub output: Receiver<i64>,
+    handle: JoinHandle<Result<IntComputer<M>, IntcodeError>>,
+}
+
+impl<M: Memory + Send + 'static> IntComputer<M> {
+    // Run the program on a new thread, input blocks until a value is sent
+    pub fn spawn(self) -> ComputerThread<M> {
+        let (input, input_rx) = mpsc::channel();
+        let (output_tx, output) = mpsc::channel();
+        ComputerThread {
+            input,
+            output,
+            handle: self.spawn_with(input_rx, output_tx),
+        }
+    }
+
+    // Run the program on a new thread reading from and writing to the given channels,
+    // which allows connecting several computers directly
+    // The thread ends once the program finished, exhausted its budget or the input channel
+    // disconnected, the computer is handed back without the channels
+    pub fn spawn_with(
+        mut self,
+        input: Receiver<i64>,
+        output: Sender<i64>,
+    ) -> JoinHandle<Result<IntComputer<M>, IntcodeError>> {
+        thread::spawn(move || {
+            self.set_input_source(BlockingInput::new(input));
+            self.set_output_sink(output);
+            let result = loop {
+                match self.run() {
+                    Ok(ProgramState::Finished)
+                    | Ok(ProgramState::WaitingForInput)
+                    | Ok(ProgramState::BudgetExhausted) => break Ok(()),
+                    Ok(_) => continue,
+                    Err(error) => break Err(error),
+                }
+            };
+            self.clear_input_source();
+            self.clear_output_sink();
+            result.map(|_| self)
+        })
+    }
+}
+
+impl<M> ComputerThread<M> {
+    // Wait for the program to end, dropping the input channel first so a program waiting for
+    // more input stops
+    pub fn join(self) -> Result<IntComputer<M>, IntcodeError> {
+        drop(self.input);
+        self.handle.join().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{ECHO, FEEDBACK};
+
+    #[test]
+    fn test_spawn() {
+        let thread = IntComputer::new(ECHO.to_vec()).spawn();
+        for value in 1..=3 {
+            thread.input.send(value).unwrap();
+            assert_eq!(thread.output.recv(), Ok(value));
+        }
+        let comp = thread.join().unwrap();
+        assert_eq!(comp.state, ProgramState::WaitingForInput);
+        assert_eq!(comp.peek(9), 3);
+    }
+
+    #[test]
+    fn test_feedback_loop() {
+        let (first, mut rx) = mpsc::channel();
+        let mut handles = vec![];
+        for &phase in [9, 8, 7, 6, 5].iter() {
+            let (tx, next_rx) = mpsc::channel();
+            let mut comp = IntComputer::new(FEEDBACK.to_vec());
+            comp.input.push_back(phase);
+            handles.push(comp.spawn_with(rx, tx));
+            rx = next_rx;
+        }
+
+        // Feed the output of the last amplifier back into the first one until it finished
+        first.send(0).unwrap();
+        let mut last = 0;
+        for value in rx.iter() {
+            last = value;
+            let _ = first.send(value);
+        }
+        for handle in handles {
+            assert_eq!(
+                handle.join().unwrap().unwrap().state,
+                ProgramState::Finished
+            );
+        }
+        assert_eq!(last, 139629729);
+    }
+}
@@ -0,0 +1,284 @@
+use crate::Observer;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex};
+
+// Trace files start with the magic and a format version, followed by the entries
+const MAGIC: &[u8; 4] = b"ICTR";
+const VERSION: u8 = 1;
+
+// Executed instruction with the relative base before execution, the values of all read
+// operands in order and the value written to memory, if any
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceEntry {
+    pub pc: usize,
+    pub opcode: i64,
+    pub base: i64,
+    pub operands: Vec<i64>,
+    pub result: Option<i64>,
+}
+
+// First position at which two traces differ, None marks the end of the shorter trace
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub index: usize,
+    pub left: Option<TraceEntry>,
+    pub right: Option<TraceEntry>,
+}
+
+struct WriterState<W> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+// Observer streaming the executed instructions to a writer in the binary trace format
+// Clones share the writer, keep one to call finish once the program stopped
+pub struct TraceWriter<W> {
+    state: Arc<Mutex<WriterState<W>>>,
+}
+
+// Iterator over the entries of a binary trace
+pub struct TraceReader<R> {
+    reader: R,
+}
+
+fn read_i64<R: Read>(reader: &mut R) -> io::Result<i64> {
+    let mut buf = [0; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(i64::from_le_bytes(buf))
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> io::Result<u8> {
+    let mut buf = [0; 1];
+    reader.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+impl TraceEntry {
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&(self.pc as u64).to_le_bytes())?;
+        writer.write_all(&self.opcode.to_le_bytes())?;
+        writer.write_all(&self.base.to_le_bytes())?;
+        writer.write_all(&[self.operands.len() as u8])?;
+        for operand in self.operands.iter() {
+            writer.write_all(&operand.to_le_bytes())?;
+        }
+        match self.result {
+            Some(result) => {
+                writer.write_all(&[1])?;
+                writer.write_all(&result.to_le_bytes())
+            }
+            None => writer.write_all(&[0]),
+        }
+    }
+
+    // Read the next entry, None at the end of the trace
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Option<TraceEntry>> {
+        let mut pc = [0; 8];
+        match reader.read(&mut pc[..1])? {
+            0 => return Ok(None),
+            _ => reader.read_exact(&mut pc[1..])?,
+        }
+        let pc = u64::from_le_bytes(pc) as usize;
+        let opcode = read_i64(reader)?;
+        let base = read_i64(reader)?;
+        let operands = (0..read_u8(reader)?)
+            .map(|_| read_i64(reader))
+            .collect::<io::Result<Vec<i64>>>()?;
+        let result = match read_u8(reader)? {
+            0 => None,
+            _ => Some(read_i64(reader)?),
+        };
+        Ok(Some(TraceEntry {
+            pc,
+            opcode,
+            base,
+            operands,
+            result,
+        }))
+    }
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:04}: {} base={} operands={:?}",
+            self.pc, self.opcode, self.base, self.operands
+        )?;
+        if let Some(result) = self.result {
+            write!(f, " -> {}", result)?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> TraceWriter<W> {
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        Ok(Self {
+            state: Arc::new(Mutex::new(WriterState {
+                writer,
+                error: None,
+            })),
+        })
+    }
+
+    // Flush the writer and report the first error that occurred while recording
+    pub fn finish(&self) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(error) = state.error.take() {
+            return Err(error);
+        }
+        state.writer.flush()
+    }
+
+    // Take back the writer, None while the computer still holds a clone
+    pub fn into_inner(self) -> Option<W> {
+        let state = Arc::try_unwrap(self.state).ok()?;
+        Some(state.into_inner().unwrap().writer)
+    }
+}
+
+impl<W> Clone for TraceWriter<W> {
+    fn clone(&self) -> Self {
+        Self {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl<W: Write> Observer for TraceWriter<W> {
+    fn on_instruction(&mut self, entry: &TraceEntry) {
+        let mut state = self.state.lock().unwrap();
+        if state.error.is_none() {
+            if let Err(error) = entry.write_to(&mut state.writer) {
+                state.error = Some(error);
+            }
+        }
+    }
+}
+
+impl<R: Read> TraceReader<R> {
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut header = [0; 5];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != MAGIC || header[4] != VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not an Intcode trace",
+            ));
+        }
+        Ok(Self { reader })
+    }
+}
+
+impl<R: Read> Iterator for TraceReader<R> {
+    type Item = io::Result<TraceEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        TraceEntry::read_from(&mut self.reader).transpose()
+    }
+}
+
+// Compare two traces entry by entry, None if they are identical
+pub fn diff<A, B>(left: A, right: B) -> io::Result<Option<Divergence>>
+where
+    A: IntoIterator<Item = io::Result<TraceEntry>>,
+    B: IntoIterator<Item = io::Result<TraceEntry>>,
+{
+    let mut left = left.into_iter();
+    let mut right = right.into_iter();
+    let mut index = 0;
+    loop {
+        let l = left.next().transpose()?;
+        let r = right.next().transpose()?;
+        if l.is_none() && r.is_none() {
+            return Ok(None);
+        }
+        if l != r {
+            return Ok(Some(Divergence {
+                index,
+                left: l,
+                right: r,
+            }));
+        }
+        index += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IntComputer;
+    use std::io::Cursor;
+
+    // Record the trace of a program into memory
+    fn record(program: Vec<i64>, input: &[i64]) -> Vec<u8> {
+        let writer = TraceWriter::new(vec![]).unwrap();
+        let mut comp = IntComputer::new(program);
+        comp.input.extend(input);
+        comp.add_observer(writer.clone());
+        comp.run().unwrap();
+        writer.finish().unwrap();
+
+        drop(comp);
+        writer.into_inner().unwrap()
+    }
+
+    fn entries(trace: Vec<u8>) -> Vec<TraceEntry> {
+        TraceReader::new(Cursor::new(trace))
+            .unwrap()
+            .collect::<io::Result<Vec<TraceEntry>>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_record() {
+        let trace = record(vec![3, 9, 109, 4, 22201, 5, 5, 0, 99, 0], &[21]);
+        let entry = |pc, opcode, base, operands: Vec<i64>, result| TraceEntry {
+            pc,
+            opcode,
+            base,
+            operands,
+            result,
+        };
+        assert_eq!(
+            entries(trace),
+            vec![
+                entry(0, 3, 0, vec![], Some(21)),
+                entry(2, 109, 0, vec![4], None),
+                entry(4, 22201, 4, vec![21, 21], Some(42)),
+                entry(8, 99, 4, vec![], None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let program = vec![3, 11, 1002, 11, 3, 11, 4, 11, 99, 0, 0, 0];
+        let a = || TraceReader::new(Cursor::new(record(program.clone(), &[5]))).unwrap();
+        let b = TraceReader::new(Cursor::new(record(program.clone(), &[6]))).unwrap();
+        assert_eq!(diff(a(), a()).unwrap(), None);
+
+        let divergence = diff(a(), b).unwrap().unwrap();
+        assert_eq!(divergence.index, 0);
+        assert_eq!(divergence.left.unwrap().result, Some(5));
+        assert_eq!(divergence.right.unwrap().result, Some(6));
+
+        // A shorter trace diverges where it ends
+        let short = entries(record(program.clone(), &[5]))
+            .into_iter()
+            .take(2)
+            .map(Ok);
+        let divergence = diff(a(), short).unwrap().unwrap();
+        assert_eq!(divergence.index, 2);
+        assert_eq!(divergence.right, None);
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        assert!(TraceReader::new(Cursor::new(b"nope!".to_vec())).is_err());
+    }
+}
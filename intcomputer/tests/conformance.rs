@@ -0,0 +1,121 @@
+// Example programs of the puzzle descriptions, run as described in tests/corpus/manifest.json
+//
+// Every case names a program file in the corpus and the input it gets. Its output, the memory
+// after it finished or both are compared to the expected values. Cases with phases run one
+// amplifier per phase setting like on day 7, connected in a line or with feedback.
+
+use intcomputer::{
+    load_program, CompiledEngine, IntComputer, Network, Pipeline, ProgramState, Ring, Topology,
+};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+const CORPUS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus");
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Case {
+    program: String,
+    #[serde(default)]
+    input: Vec<i64>,
+    output: Option<Vec<i64>>,
+    memory: Option<Vec<i64>>,
+    phases: Option<Vec<i64>>,
+    #[serde(default)]
+    feedback: bool,
+}
+
+fn cases() -> Vec<Case> {
+    let manifest = fs::read_to_string(Path::new(CORPUS).join("manifest.json")).unwrap();
+    serde_json::from_str(&manifest).unwrap()
+}
+
+fn check(case: &Case, comp: &IntComputer, engine: &str) {
+    if let Some(output) = &case.output {
+        assert_eq!(
+            comp.output, *output,
+            "output of {} on {}",
+            case.program, engine
+        );
+    }
+    if let Some(memory) = &case.memory {
+        assert_eq!(
+            comp.program, *memory,
+            "memory of {} on {}",
+            case.program, engine
+        );
+    }
+}
+
+// Day 7 amplifiers, the first one also receives a 0 after its phase setting
+fn run_amplifiers<T: Topology>(program: &[i64], phases: &[i64], topology: T) -> Vec<i64> {
+    let machines = phases
+        .iter()
+        .map(|&phase| {
+            let mut comp = IntComputer::new(program.to_vec());
+            comp.input.push_back(phase);
+            comp
+        })
+        .collect();
+    let mut network = Network::new(machines, topology);
+    network.machine_mut(0).input.push_back(0);
+    network.run().unwrap();
+    network.output.into_iter().collect()
+}
+
+#[test]
+fn test_corpus() {
+    let cases = cases();
+    assert!(!cases.is_empty());
+    for case in cases.iter() {
+        assert!(
+            case.output.is_some() || case.memory.is_some(),
+            "{} has no expected result",
+            case.program
+        );
+        let program = load_program(Path::new(CORPUS).join(&case.program)).unwrap();
+
+        if let Some(phases) = &case.phases {
+            let output = if case.feedback {
+                run_amplifiers(&program, phases, Ring)
+            } else {
+                run_amplifiers(&program, phases, Pipeline)
+            };
+            assert_eq!(Some(output), case.output, "output of {}", case.program);
+            continue;
+        }
+
+        let mut comp = IntComputer::new(program.clone());
+        comp.input.extend(&case.input);
+        assert_eq!(comp.run(), Ok(ProgramState::Finished), "{}", case.program);
+        check(case, &comp, "the interpreter");
+
+        let mut comp = IntComputer::new(program);
+        comp.input.extend(&case.input);
+        let result = CompiledEngine::new().run(&mut comp);
+        assert_eq!(result, Ok(ProgramState::Finished), "{}", case.program);
+        check(case, &comp, "the compiled engine");
+    }
+}
+
+#[test]
+fn test_corpus_is_complete() {
+    // Every program in the corpus is used by a case
+    let cases = cases();
+    for day in fs::read_dir(CORPUS).unwrap() {
+        let day = day.unwrap().path();
+        if !day.is_dir() {
+            continue;
+        }
+        for file in fs::read_dir(&day).unwrap() {
+            let path = file.unwrap().path();
+            let name = path.strip_prefix(CORPUS).unwrap().to_str().unwrap();
+            assert!(
+                cases.iter().any(|case| case.program == name),
+                "{} is not in the manifest",
+                name
+            );
+        }
+    }
+}
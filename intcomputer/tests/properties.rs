@@ -0,0 +1,226 @@
+// Properties of IntComputer checked on generated programs
+//
+// Random programs are mostly made of valid opcodes and small values so they get past the
+// first few instructions, assembled programs only jump forward and write to their data
+// section, so they always terminate and can also run on the compiled engine.
+
+mod reference;
+
+use intcomputer::asm::assemble;
+use intcomputer::{CompiledEngine, IntComputer, IntcodeError, Memory, PagedMemory, ProgramState};
+use proptest::prelude::*;
+use reference::Reference;
+
+const BUDGET: u64 = 10_000;
+const MEMORY_LIMIT: usize = 1 << 16;
+const DATA_CELLS: usize = 4;
+
+#[derive(Debug, Clone)]
+enum Operand {
+    Immediate(i64),
+    Data(usize),
+    Relative(i64),
+}
+
+#[derive(Debug, Clone)]
+enum Line {
+    Arith(&'static str, Operand, Operand, usize),
+    In(usize),
+    Out(Operand),
+    // Jumps skip the given number of lines ahead
+    Jump(&'static str, Operand, usize),
+    AdjBase(Operand),
+}
+
+fn mode() -> impl Strategy<Value = i64> {
+    prop_oneof![8 => 0..3i64, 1 => 3..10i64]
+}
+
+fn word() -> impl Strategy<Value = i64> {
+    let ops = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 99];
+    prop_oneof![
+        4 => (prop::sample::select(ops), mode(), mode(), mode())
+            .prop_map(|(op, m1, m2, m3)| op + 100 * m1 + 1000 * m2 + 10000 * m3),
+        4 => -8..64i64,
+        1 => any::<i64>(),
+    ]
+}
+
+fn random_program() -> impl Strategy<Value = Vec<i64>> {
+    prop::collection::vec(word(), 1..64)
+}
+
+fn operand() -> impl Strategy<Value = Operand> {
+    prop_oneof![
+        (-5..100i64).prop_map(Operand::Immediate),
+        (0..DATA_CELLS).prop_map(Operand::Data),
+        (-2..8i64).prop_map(Operand::Relative),
+    ]
+}
+
+fn line() -> impl Strategy<Value = Line> {
+    prop_oneof![
+        (
+            prop::sample::select(vec!["ADD", "MUL", "LT", "EQ"]),
+            operand(),
+            operand(),
+            0..DATA_CELLS
+        )
+            .prop_map(|(op, a, b, dst)| Line::Arith(op, a, b, dst)),
+        (0..DATA_CELLS).prop_map(Line::In),
+        operand().prop_map(Line::Out),
+        (prop::sample::select(vec!["JT", "JF"]), operand(), 0..4usize)
+            .prop_map(|(op, cond, skip)| Line::Jump(op, cond, skip)),
+        operand().prop_map(Line::AdjBase),
+    ]
+}
+
+fn render(operand: &Operand) -> String {
+    match operand {
+        Operand::Immediate(value) => format!("#{}", value),
+        Operand::Data(cell) => format!("[d{}]", cell),
+        Operand::Relative(offset) if *offset < 0 => format!("[rb-{}]", -offset),
+        Operand::Relative(offset) => format!("[rb+{}]", offset),
+    }
+}
+
+fn source(lines: &[Line], data: &[i64]) -> String {
+    let mut source = String::new();
+    for (idx, line) in lines.iter().enumerate() {
+        let instr = match line {
+            Line::Arith(op, a, b, dst) => {
+                format!("{} {}, {} -> [d{}]", op, render(a), render(b), dst)
+            }
+            Line::In(dst) => format!("IN -> [d{}]", dst),
+            Line::Out(src) => format!("OUT {}", render(src)),
+            Line::Jump(op, cond, skip) => {
+                let target = (idx + 1 + skip).min(lines.len());
+                format!("{} {}, #l{}", op, render(cond), target)
+            }
+            Line::AdjBase(src) => format!("ARB {}", render(src)),
+        };
+        source.push_str(&format!("l{}: {}\n", idx, instr));
+    }
+    source.push_str(&format!("l{}: HLT\n", lines.len()));
+    for (cell, value) in data.iter().enumerate() {
+        source.push_str(&format!("d{}: .data {}\n", cell, value));
+    }
+    source
+}
+
+fn assembled_program() -> impl Strategy<Value = Vec<i64>> {
+    (
+        prop::collection::vec(line(), 0..32),
+        prop::collection::vec(-10..10i64, DATA_CELLS),
+    )
+        .prop_map(|(lines, data)| assemble(&source(&lines, &data)).unwrap())
+}
+
+fn any_program() -> impl Strategy<Value = Vec<i64>> {
+    prop_oneof![random_program(), assembled_program()]
+}
+
+fn inputs() -> impl Strategy<Value = Vec<i64>> {
+    prop::collection::vec(-100..100i64, 0..8)
+}
+
+fn computer(program: Vec<i64>, input: &[i64]) -> IntComputer {
+    let mut comp = IntComputer::new(program);
+    comp.set_budget(Some(BUDGET));
+    comp.set_memory_limit(Some(MEMORY_LIMIT));
+    comp.input.extend(input);
+    comp
+}
+
+// Run the reference interpreter with the same budget and limit as computer
+fn reference(program: Vec<i64>, input: &[i64]) -> (Reference, Result<ProgramState, IntcodeError>) {
+    let mut reference = Reference::new(program);
+    reference.memory_limit = MEMORY_LIMIT;
+    reference.input.extend(input);
+    let result = reference.run(BUDGET);
+    (reference, result)
+}
+
+// Step until the program stops, checking that memory never gets smaller
+fn step_growing<M: Memory>(comp: &mut IntComputer<M>) -> Result<(), TestCaseError> {
+    let (mut size, mut allocated) = (comp.program.size(), comp.program.allocated());
+    loop {
+        let result = comp.step();
+        prop_assert!(comp.program.size() >= size);
+        prop_assert!(comp.program.allocated() >= allocated);
+        size = comp.program.size();
+        allocated = comp.program.allocated();
+        if result != Ok(ProgramState::Running) {
+            return Ok(());
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn matches_reference(program in any_program(), input in inputs()) {
+        let mut comp = computer(program.clone(), &input);
+        let result = comp.run();
+        let (expected, expected_result) = reference(program, &input);
+
+        prop_assert_eq!(result, expected_result);
+        prop_assert_eq!(comp.pc(), expected.pc);
+        prop_assert_eq!(comp.base(), expected.base);
+        prop_assert_eq!(&comp.program, &expected.memory);
+        prop_assert_eq!(comp.output.iter().cloned().collect::<Vec<_>>(), expected.output);
+        prop_assert_eq!(comp.input, expected.input);
+    }
+
+    #[test]
+    fn compiled_matches_interpreter(program in assembled_program(), input in inputs()) {
+        // Without a budget, which makes the compiled engine fall back to the interpreter
+        let mut comp = IntComputer::new(program.clone());
+        comp.input.extend(&input);
+        let result = comp.run();
+
+        let mut compiled = IntComputer::new(program);
+        compiled.input.extend(&input);
+        let compiled_result = CompiledEngine::new().run(&mut compiled);
+
+        prop_assert_eq!(compiled_result, result);
+        prop_assert_eq!(compiled.pc(), comp.pc());
+        prop_assert_eq!(compiled.base(), comp.base());
+        prop_assert_eq!(compiled.program, comp.program);
+        prop_assert_eq!(compiled.output, comp.output);
+    }
+
+    #[test]
+    fn resumes_after_waiting_for_input(program in any_program(), input in inputs()) {
+        let mut comp = computer(program.clone(), &input);
+        let result = comp.run();
+
+        // Feed the same values one at a time
+        let mut resumed = computer(program, &[]);
+        let mut values = input.iter();
+        let resumed_result = loop {
+            let result = resumed.run();
+            match (result.clone(), values.next()) {
+                (Ok(ProgramState::WaitingForInput), Some(&value)) => {
+                    resumed.input.push_back(value)
+                }
+                _ => break result,
+            }
+        };
+
+        prop_assert_eq!(resumed_result, result);
+        prop_assert_eq!(resumed.pc(), comp.pc());
+        prop_assert_eq!(resumed.program, comp.program);
+        prop_assert_eq!(resumed.output, comp.output);
+    }
+
+    #[test]
+    fn memory_never_shrinks(program in any_program(), input in inputs()) {
+        // The backends may disagree on exceeding the limit, which the paged one counts in pages
+        let mut paged = IntComputer::with_memory(PagedMemory::new(&program));
+        paged.set_budget(Some(BUDGET));
+        paged.set_memory_limit(Some(MEMORY_LIMIT));
+        paged.input.extend(&input);
+        step_growing(&mut computer(program, &input))?;
+        step_growing(&mut paged)?;
+    }
+}
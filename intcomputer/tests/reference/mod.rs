@@ -0,0 +1,154 @@
+// Straightforward Intcode interpreter written against the puzzle description, used as an
+// oracle for IntComputer
+//
+// It has no caches, observers or alternative backends. Faults are reported with the same
+// errors as IntComputer, in the order in which IntComputer detects them.
+
+use intcomputer::{IntcodeError, ProgramState};
+use std::collections::VecDeque;
+
+pub struct Reference {
+    pub memory: Vec<i64>,
+    pub pc: usize,
+    pub base: i64,
+    pub input: VecDeque<i64>,
+    pub output: Vec<i64>,
+    pub memory_limit: usize,
+}
+
+impl Reference {
+    pub fn new(program: Vec<i64>) -> Reference {
+        Reference {
+            memory: program,
+            pc: 0,
+            base: 0,
+            input: VecDeque::new(),
+            output: vec![],
+            memory_limit: intcomputer::DEFAULT_MEMORY_LIMIT,
+        }
+    }
+
+    // Run until the program halts, waits for input or executed budget instructions
+    pub fn run(&mut self, budget: u64) -> Result<ProgramState, IntcodeError> {
+        let mut executed = 0;
+        loop {
+            if executed == budget {
+                return Ok(ProgramState::BudgetExhausted);
+            }
+            let opcode = self.fetch(self.pc)?;
+            let pc = self.pc;
+            match opcode % 100 {
+                op @ (1 | 2 | 7 | 8) => {
+                    let a = self.read(opcode, 1)?;
+                    let b = self.read(opcode, 2)?;
+                    let result = match op {
+                        1 => a.wrapping_add(b),
+                        2 => a.wrapping_mul(b),
+                        7 => (a < b) as i64,
+                        _ => (a == b) as i64,
+                    };
+                    self.write(opcode, 3, result)?;
+                    self.pc += 4;
+                }
+                3 => {
+                    let value = match self.input.front() {
+                        Some(&value) => value,
+                        None => return Ok(ProgramState::WaitingForInput),
+                    };
+                    self.write(opcode, 1, value)?;
+                    self.input.pop_front();
+                    self.pc += 2;
+                }
+                4 => {
+                    let value = self.read(opcode, 1)?;
+                    self.output.push(value);
+                    self.pc += 2;
+                }
+                op @ (5 | 6) => {
+                    let cond = self.read(opcode, 1)?;
+                    let dst = self.read(opcode, 2)?;
+                    if (cond != 0) == (op == 5) {
+                        if dst < 0 {
+                            return Err(IntcodeError::NegativeAddress { pc, address: dst });
+                        }
+                        self.pc = dst as usize;
+                    } else {
+                        self.pc += 3;
+                    }
+                }
+                9 => {
+                    let value = self.read(opcode, 1)?;
+                    self.base = self.base.wrapping_add(value);
+                    self.pc += 2;
+                }
+                99 => return Ok(ProgramState::Finished),
+                _ => return Err(IntcodeError::UnknownOpcode { pc, value: opcode }),
+            }
+            executed += 1;
+        }
+    }
+
+    fn fetch(&self, addr: usize) -> Result<i64, IntcodeError> {
+        self.memory
+            .get(addr)
+            .cloned()
+            .ok_or(IntcodeError::PcOutOfBounds { pc: addr })
+    }
+
+    fn param(&self, n: usize) -> Result<i64, IntcodeError> {
+        self.fetch(self.pc + n)
+            .map_err(|_| IntcodeError::TruncatedInstruction { pc: self.pc })
+    }
+
+    fn mode(opcode: i64, n: usize) -> i64 {
+        opcode / 10i64.pow(n as u32 + 1) % 10
+    }
+
+    fn address(&self, opcode: i64, n: usize) -> Result<usize, IntcodeError> {
+        let word = self.param(n)?;
+        let address = match Reference::mode(opcode, n) {
+            0 => word,
+            2 => self.base.wrapping_add(word),
+            _ => {
+                return Err(IntcodeError::InvalidParameterMode {
+                    pc: self.pc,
+                    value: opcode,
+                })
+            }
+        };
+        if address < 0 {
+            return Err(IntcodeError::NegativeAddress {
+                pc: self.pc,
+                address,
+            });
+        }
+        Ok(address as usize)
+    }
+
+    fn read(&self, opcode: i64, n: usize) -> Result<i64, IntcodeError> {
+        if Reference::mode(opcode, n) == 1 {
+            return self.param(n);
+        }
+        let address = self.address(opcode, n)?;
+        Ok(self.memory.get(address).cloned().unwrap_or(0))
+    }
+
+    fn write(&mut self, opcode: i64, n: usize, value: i64) -> Result<(), IntcodeError> {
+        if Reference::mode(opcode, n) == 1 {
+            return Err(IntcodeError::ImmediateWrite { pc: self.pc });
+        }
+        let address = self.address(opcode, n)?;
+        if address >= self.memory.len() && address + 1 > self.memory_limit {
+            return Err(IntcodeError::MemoryLimitExceeded {
+                pc: self.pc,
+                address,
+                limit: self.memory_limit,
+            });
+        }
+        if address >= self.memory.len() {
+            self.memory.resize(address + 1, 0);
+        }
+        self.memory[address] = value;
+        Ok(())
+    }
+}